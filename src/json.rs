@@ -4,7 +4,33 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::{error::Error, fmt::Display};
+pub mod borrowed;
+pub mod cst;
+mod diff;
+mod encoding;
+mod number;
+mod object;
+pub mod patch;
+pub mod path;
+mod pointer;
+mod stream;
+mod value;
+mod writer;
+
+use std::{
+    borrow::Cow,
+    collections::{HashSet, VecDeque},
+    error::Error,
+    fmt::Display,
+    ops::Range,
+};
+
+pub use diff::{diff, diff_with_options, ArrayMatching, Change, DiffOptions, JsonDiff};
+pub use encoding::Encoding;
+pub use object::JsonObject;
+pub use stream::{JsonPushParser, JsonStreamReader, PushTokens, StreamError};
+pub use value::{JsonNumber, JsonValue};
+pub use writer::{copy_tokens, CopyError, JsonTextWriter, WriteError, WriterOptions};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SyntaxError {
@@ -13,7 +39,26 @@ pub enum SyntaxError {
     UnterminatedString,
     UnterminatedArray,
     UnterminatedObject,
+    InvalidMemberName,
     InvalidMemberValueDelimiter,
+    UnexpectedComment,
+    SingleQuotedString,
+    UnquotedString,
+    InvalidNumber,
+    InvalidEscape,
+    ControlCharacter,
+    TrailingComma,
+    TrailingContent,
+    LoneSurrogate,
+    InvalidUtf8,
+    InvalidEncoding,
+    DepthLimitExceeded,
+    InputLimitExceeded,
+    StringLimitExceeded,
+    NumberLimitExceeded,
+    MemberLimitExceeded,
+    DuplicateKey,
+    UnexpectedCharacter,
 }
 
 impl Display for SyntaxError {
@@ -25,23 +70,292 @@ impl Display for SyntaxError {
 impl Error for SyntaxError {}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NumberError {
+    NotANumber,
+    Invalid,
+    Overflow,
+    Lossy,
+}
+
+impl Display for NumberError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl Error for NumberError {}
+
+/// A location in the source text. The line and column are 1-based. The
+/// column counts characters (Unicode scalar values) so a tab advances it by
+/// one, just like any other character. A line break is any of CR, LF or
+/// CRLF, where the latter counts as a single break.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Position {
+    /// The byte offset from the start of the source.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self { offset: 0, line: 1, column: 1 }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// The region of the source text covered by a token, where `end` is the
+/// position just past its last character.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Span {
+    start: Position,
+    end: Position,
+}
+
+impl Span {
+    pub fn start(&self) -> Position {
+        self.start
+    }
+
+    pub fn end(&self) -> Position {
+        self.end
+    }
+
+    pub fn len(&self) -> usize {
+        self.end.offset - self.start.offset
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    kind: SyntaxError,
+    position: Position,
+}
+
+impl ParseError {
+    fn new(kind: SyntaxError, position: Position) -> Self {
+        Self { kind, position }
+    }
+
+    pub fn kind(&self) -> SyntaxError {
+        self.kind
+    }
+
+    /// The position at which the reader gave up.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.kind, self.position)
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+/// A token read from the source. Its text is borrowed from the source where
+/// possible, otherwise owned.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct JsonToken<'s> {
     kind: JsonTokenKind,
-    text: &'s str,
+    text: Cow<'s, str>,
+    span: Span,
 }
 
 impl<'s> JsonToken<'s> {
-    fn new(kind: JsonTokenKind, text: &'s str) -> Self {
-        Self { kind, text }
+    fn new(kind: JsonTokenKind, text: Cow<'s, str>, span: Span) -> Self {
+        Self { kind, text, span }
     }
 
     pub fn kind(&self) -> JsonTokenKind {
         self.kind
     }
 
-    pub fn text(&self) -> &'s str {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The text borrowed from the source, for as long as the source lives,
+    /// unless the token owns its text, as tokens read from a
+    /// [`JsonStreamReader`] or [`JsonPushParser`] may.
+    pub fn as_borrowed_text(&self) -> Option<&'s str> {
+        match self.text {
+            Cow::Borrowed(text) => Some(text),
+            Cow::Owned(_) => None,
+        }
+    }
+
+    pub fn into_text(self) -> Cow<'s, str> {
         self.text
     }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The text of a [`JsonTokenKind::Comment`] token without the `//`,
+    /// `/*` and `*/`, or `#` that delimit it.
+    pub fn comment_text(&self) -> Option<&str> {
+        Some(match self.comment_style()? {
+            CommentStyle::Line => &self.text[2..],
+            CommentStyle::Block => &self.text[2..self.text.len() - 2],
+            CommentStyle::Hash => &self.text[1..],
+        })
+    }
+
+    /// The style of a [`JsonTokenKind::Comment`] token.
+    pub fn comment_style(&self) -> Option<CommentStyle> {
+        if self.kind != JsonTokenKind::Comment {
+            return None;
+        }
+        Some(match &self.text[..1] {
+            "#" => CommentStyle::Hash,
+            _ if self.text.starts_with("/*") => CommentStyle::Block,
+            _ => CommentStyle::Line,
+        })
+    }
+
+    /// Returns a token that owns its text so it can outlive the source.
+    pub fn into_owned(self) -> JsonToken<'static> {
+        JsonToken::new(self.kind, Cow::Owned(self.text.into_owned()), self.span)
+    }
+
+    /// Returns a part of the text, borrowed from the source if the text is.
+    fn slice(&self, range: Range<usize>) -> Cow<'s, str> {
+        match &self.text {
+            Cow::Borrowed(text) => Cow::Borrowed(&text[range]),
+            Cow::Owned(text) => Cow::Owned(text[range].to_owned()),
+        }
+    }
+
+    fn number_text(&self) -> Result<&str, NumberError> {
+        match self.kind {
+            JsonTokenKind::Number => Ok(&self.text),
+            _ => Err(NumberError::NotANumber),
+        }
+    }
+
+    /// Returns the value of a [`JsonTokenKind::Number`] token as an integer.
+    /// A number with a fraction or exponent converts as long as its value is
+    /// integral, as in `1.0` or `1e3`, otherwise the conversion is
+    /// [`NumberError::Lossy`]. The non-standard hexadecimal (`0x1F`) and
+    /// octal (`017`) forms are converted according to their radix, but an
+    /// integer with a leading zero and an 8 or 9 among its digits, as in
+    /// `08`, is [`NumberError::Invalid`].
+    pub fn as_i128(&self) -> Result<i128, NumberError> {
+        number::to_i128(self.number_text()?)
+    }
+
+    /// See [`JsonToken::as_i128`].
+    pub fn as_i64(&self) -> Result<i64, NumberError> {
+        self.as_i128().and_then(|n| i64::try_from(n).map_err(|_| NumberError::Overflow))
+    }
+
+    /// See [`JsonToken::as_i128`].
+    pub fn as_u64(&self) -> Result<u64, NumberError> {
+        self.as_i128().and_then(|n| u64::try_from(n).map_err(|_| NumberError::Overflow))
+    }
+
+    /// Returns the value of a [`JsonTokenKind::Number`] token as the nearest
+    /// floating-point number. A finite number that is too large to be
+    /// represented is an [`NumberError::Overflow`].
+    pub fn as_f64(&self) -> Result<f64, NumberError> {
+        number::to_f64(self.number_text()?)
+    }
+
+    /// Returns the text of a [`JsonTokenKind::Number`] token, with all its
+    /// digits, in the decimal form specified by RFC 8259. The text is
+    /// borrowed from the source when it is already in that form.
+    /// Non-standard forms are rewritten, so `+.5` becomes `0.5` and `0x1F`
+    /// becomes `31`.
+    pub fn as_decimal_str(&self) -> Result<Cow<'s, str>, NumberError> {
+        Ok(match number::to_decimal_str(self.number_text()?)? {
+            Cow::Borrowed(text) => self.slice(0..text.len()),
+            Cow::Owned(text) => Cow::Owned(text),
+        })
+    }
+
+    /// Returns the value of a [`JsonTokenKind::String`] or
+    /// [`JsonTokenKind::ObjectMember`] token, with the quotes removed and
+    /// escape sequences decoded, borrowing from the source if there are no
+    /// escape sequences. An unknown escape sequence, like `\q`, stands for
+    /// the character itself. Text without quotes, such as that of other
+    /// kinds of tokens, is returned as is.
+    pub fn as_str(&self) -> Result<Cow<'s, str>, ParseError> {
+        let text = &*self.text;
+        match text.chars().next() {
+            Some(quote @ ('"' | '\'')) if text.len() > 1 && text.ends_with(quote) => {}
+            _ => return Ok(self.slice(0..text.len())),
+        }
+        let inner = &text[1..text.len() - 1];
+        if !inner.contains('\\') {
+            return Ok(self.slice(1..text.len() - 1));
+        }
+        unescape(inner).map(Cow::Owned).map_err(|(i, kind)| self.escape_error(i, kind))
+    }
+
+    /// Returns the text of a string like [`JsonToken::as_str`] does but
+    /// leaving escape sequences as they are, after checking them, along with
+    /// whether there are any.
+    fn as_raw_str(&self) -> Result<(Cow<'s, str>, bool), ParseError> {
+        let text = &*self.text;
+        match text.chars().next() {
+            Some(quote @ ('"' | '\'')) if text.len() > 1 && text.ends_with(quote) => {}
+            _ => return Ok((self.slice(0..text.len()), false)),
+        }
+        let inner = &text[1..text.len() - 1];
+        let escaped = inner.contains('\\');
+        if escaped {
+            unescape_with(inner, |_| {}).map_err(|(i, kind)| self.escape_error(i, kind))?;
+        }
+        Ok((self.slice(1..text.len() - 1), escaped))
+    }
+
+    /// The error for the escape sequence at the offset `i` of the text of a
+    /// string within the quotes.
+    fn escape_error(&self, i: usize, kind: SyntaxError) -> ParseError {
+        //
+        // Strings cannot span lines so the position of the offending
+        // escape sequence is on the same line as the token.
+        //
+        let start = self.span.start;
+        let offset = 1 + i;
+        let position = Position {
+            offset: start.offset + offset,
+            line: start.line,
+            column: start.column + self.text[..offset].chars().count(),
+        };
+        ParseError::new(kind, position)
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -56,12 +370,291 @@ pub enum JsonTokenKind {
     ObjectStart,
     ObjectEnd,
     ObjectMember,
+    /// A comment, only read if set by [`ReaderOptions::comment_tokens`] or
+    /// in trivia mode, see [`ReaderOptions::trivia`].
+    Comment,
+    /// A run of whitespace, only read in trivia mode. A byte order mark at
+    /// the start counts as whitespace.
+    Whitespace,
+}
+
+/// How a comment is written.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CommentStyle {
+    /// `// ...` up to the end of the line.
+    Line,
+    /// `/* ... */`
+    Block,
+    /// `# ...` up to the end of the line.
+    Hash,
+}
+
+/// Controls which non-standard forms [`JsonTextReader`] accepts and the
+/// limits it enforces. The default is lenient and accepts all of them whereas
+/// [`ReaderOptions::strict`] only accepts JSON text as specified by RFC 8259.
+/// Both have the same limits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReaderOptions {
+    comments: bool,
+    hash_comments: bool,
+    single_quoted_strings: bool,
+    unquoted_strings: bool,
+    semicolon_separators: bool,
+    assignment_delimiters: bool,
+    trailing_commas: bool,
+    lenient_whitespace: bool,
+    lenient_numbers: bool,
+    lenient_strings: bool,
+    trailing_content: bool,
+    lossy_utf8: bool,
+    comment_tokens: bool,
+    trivia: bool,
+    max_depth: usize,
+    max_input_length: usize,
+    max_string_length: usize,
+    max_number_length: usize,
+    max_members: usize,
+    duplicate_keys: DuplicateKeys,
+}
+
+/// What to do about an object that has more than one member with the same
+/// name, which RFC 8259 leaves open.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DuplicateKeys {
+    /// Fail reading with [`SyntaxError::DuplicateKey`].
+    Error,
+    /// Keep the value of the first member.
+    FirstWins,
+    /// Keep the value of the last member, in place of the first.
+    LastWins,
+    /// Keep all the members, as with [`JsonObject::push`].
+    KeepAll,
+}
+
+/// The default for [`ReaderOptions::max_depth`].
+const DEFAULT_MAX_DEPTH: usize = 128;
+
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        Self {
+            comments: true,
+            hash_comments: true,
+            single_quoted_strings: true,
+            unquoted_strings: true,
+            semicolon_separators: true,
+            assignment_delimiters: true,
+            trailing_commas: true,
+            lenient_whitespace: true,
+            lenient_numbers: true,
+            lenient_strings: true,
+            trailing_content: true,
+            lossy_utf8: false,
+            comment_tokens: false,
+            trivia: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_input_length: usize::MAX,
+            max_string_length: usize::MAX,
+            max_number_length: usize::MAX,
+            max_members: usize::MAX,
+            duplicate_keys: DuplicateKeys::LastWins,
+        }
+    }
 }
 
-type IdxChar = (usize, char);
+impl ReaderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options that accept nothing beyond RFC 8259.
+    pub fn strict() -> Self {
+        Self {
+            comments: false,
+            hash_comments: false,
+            single_quoted_strings: false,
+            unquoted_strings: false,
+            semicolon_separators: false,
+            assignment_delimiters: false,
+            trailing_commas: false,
+            lenient_whitespace: false,
+            lenient_numbers: false,
+            lenient_strings: false,
+            trailing_content: false,
+            lossy_utf8: false,
+            comment_tokens: false,
+            trivia: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_input_length: usize::MAX,
+            max_string_length: usize::MAX,
+            max_number_length: usize::MAX,
+            max_members: usize::MAX,
+            duplicate_keys: DuplicateKeys::LastWins,
+        }
+    }
+
+    /// Accept `// ...` and `/* ... */` comments.
+    pub fn comments(mut self, value: bool) -> Self {
+        self.comments = value;
+        self
+    }
+
+    /// Accept `# ...` comments.
+    pub fn hash_comments(mut self, value: bool) -> Self {
+        self.hash_comments = value;
+        self
+    }
+
+    /// Accept strings delimited by single quotes, as in `'foo'`.
+    pub fn single_quoted_strings(mut self, value: bool) -> Self {
+        self.single_quoted_strings = value;
+        self
+    }
+
+    /// Accept text without quotes that is neither a literal nor a number
+    /// as a string, as in `{ foo: bar }`.
+    pub fn unquoted_strings(mut self, value: bool) -> Self {
+        self.unquoted_strings = value;
+        self
+    }
+
+    /// Accept `;` in place of `,` between array elements and object members.
+    pub fn semicolon_separators(mut self, value: bool) -> Self {
+        self.semicolon_separators = value;
+        self
+    }
+
+    /// Accept `=` and `=>` in place of `:` between a member name and value.
+    pub fn assignment_delimiters(mut self, value: bool) -> Self {
+        self.assignment_delimiters = value;
+        self
+    }
+
+    /// Accept a separator after the last array element or object member.
+    pub fn trailing_commas(mut self, value: bool) -> Self {
+        self.trailing_commas = value;
+        self
+    }
+
+    /// Accept any control character as whitespace between tokens. When
+    /// disabled, only space, tab, LF and CR are, and any other fails with
+    /// [`SyntaxError::UnexpectedCharacter`].
+    pub fn lenient_whitespace(mut self, value: bool) -> Self {
+        self.lenient_whitespace = value;
+        self
+    }
+
+    /// Accept any number form that [`f64::from_str`] does, such as `+1`,
+    /// `.5`, `1.` or `NaN`. When disabled, such forms are read as unquoted
+    /// strings, if those are accepted, otherwise they are an error.
+    ///
+    /// [`f64::from_str`]: std::str::FromStr::from_str
+    pub fn lenient_numbers(mut self, value: bool) -> Self {
+        self.lenient_numbers = value;
+        self
+    }
+
+    /// Accept unknown escape sequences and control characters (other than
+    /// line breaks, which are never accepted) in strings.
+    pub fn lenient_strings(mut self, value: bool) -> Self {
+        self.lenient_strings = value;
+        self
+    }
+
+    /// Ignore whatever follows the value at the top level. When disabled,
+    /// only whitespace (and comments, if accepted) may follow.
+    pub fn trailing_content(mut self, value: bool) -> Self {
+        self.trailing_content = value;
+        self
+    }
+
+    /// When reading bytes given to [`JsonTextReader::from_bytes`], replace
+    /// each invalid UTF-8 sequence inside a string with U+FFFD rather than
+    /// failing with [`SyntaxError::InvalidUtf8`]. Invalid sequences outside
+    /// of strings are always an error.
+    pub fn lossy_utf8(mut self, value: bool) -> Self {
+        self.lossy_utf8 = value;
+        self
+    }
+
+    /// Read comments as [`JsonTokenKind::Comment`] tokens rather than
+    /// skipping them, leaving whitespace skipped.
+    pub fn comment_tokens(mut self, value: bool) -> Self {
+        self.comment_tokens = value;
+        self
+    }
+
+    /// Read comments and runs of whitespace as [`JsonTokenKind::Comment`]
+    /// and [`JsonTokenKind::Whitespace`] tokens, so that the tokens cover
+    /// the source from the start to the end of the value, or to the end of
+    /// the source if nothing but trivia follows, except for the separators
+    /// (`:`, `,`, `;`, `=` and `=>`), which are never read as tokens. Only a
+    /// separator can fall between two tokens. Line breaks that end line
+    /// comments are read as whitespace.
+    pub fn trivia(mut self, value: bool) -> Self {
+        self.trivia = value;
+        self
+    }
+
+    /// The maximum number of arrays and objects that may be nested within
+    /// one another, beyond which reading fails with
+    /// [`SyntaxError::DepthLimitExceeded`]. The default is 128.
+    pub fn max_depth(mut self, value: usize) -> Self {
+        self.max_depth = value;
+        self
+    }
+
+    /// The maximum length of the input in bytes, beyond which reading fails
+    /// with [`SyntaxError::InputLimitExceeded`]. There is no limit by default.
+    pub fn max_input_length(mut self, value: usize) -> Self {
+        self.max_input_length = value;
+        self
+    }
+
+    /// The maximum length in bytes of a string as written, including any
+    /// quotes and escape sequences, beyond which reading fails with
+    /// [`SyntaxError::StringLimitExceeded`]. This applies to member names
+    /// too. There is no limit by default.
+    pub fn max_string_length(mut self, value: usize) -> Self {
+        self.max_string_length = value;
+        self
+    }
+
+    /// The maximum length in bytes of a number as written, beyond which
+    /// reading fails with [`SyntaxError::NumberLimitExceeded`]. Unquoted
+    /// text that starts like a number and is longer is taken to be one.
+    /// There is no limit by default.
+    pub fn max_number_length(mut self, value: usize) -> Self {
+        self.max_number_length = value;
+        self
+    }
+
+    /// The maximum number of elements in an array or members in an object,
+    /// beyond which reading fails with [`SyntaxError::MemberLimitExceeded`].
+    /// There is no limit by default.
+    pub fn max_members(mut self, value: usize) -> Self {
+        self.max_members = value;
+        self
+    }
+
+    /// What to do about duplicate member names in an object. The reader
+    /// only acts on [`DuplicateKeys::Error`], comparing names after decoding
+    /// escape sequences, and otherwise leaves it to what is built from the
+    /// tokens, like a [`JsonValue`]. The default is
+    /// [`DuplicateKeys::LastWins`].
+    pub fn duplicate_keys(mut self, value: DuplicateKeys) -> Self {
+        self.duplicate_keys = value;
+        self
+    }
+}
+
+type IdxChar = (Position, char);
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum ReaderState {
+    End,
+    /// Reads the trivia that follows the value when trailing content is
+    /// accepted and trivia or comments are read as tokens.
+    Trivia,
     Parse,
     ParseArrayFirst,
     ParseArrayNext,
@@ -70,71 +663,573 @@ enum ReaderState {
     ParseObjectMemberNext,
 }
 
+#[derive(Debug, Copy, Clone, Default)]
+struct Cursor {
+    position: Position,
+    after_cr: bool,
+}
+
+impl Cursor {
+    fn advance(&mut self, ch: char) {
+        let pos = &mut self.position;
+        pos.offset += ch.len_utf8();
+        match ch {
+            '\r' => {
+                pos.line += 1;
+                pos.column = 1;
+            }
+            '\n' if self.after_cr => {}
+            '\n' => {
+                pos.line += 1;
+                pos.column = 1;
+            }
+            _ => pos.column += 1,
+        }
+        self.after_cr = ch == '\r';
+    }
+}
+
+/// An array or object open after the last token read.
+#[derive(Debug, Default)]
+struct Container {
+    /// The number of elements or members read so far.
+    length: usize,
+    /// The names of the members read so far, only kept if duplicates are an
+    /// error.
+    names: HashSet<String>,
+}
+
+/// Where a reader is in an array or object open after the last token
+/// returned, for [`JsonTextReader::current_pointer`].
+#[derive(Debug)]
+enum PathSegment {
+    /// The index of the last element, if any.
+    Index(Option<usize>),
+    /// The name of the last member, if any.
+    Name(Option<String>),
+}
+
+/// What lies past the end of the source held by a reader.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Tail {
+    End,
+    More,
+    Invalid,
+}
+
+type Lexeme = (JsonTokenKind, Span);
+
+/// Records how far a construct (a string, unquoted text or a comment) that
+/// starts at `start` was scanned without incident, so that scanning can pick
+/// up from there when it is retried after the source ran out. For unquoted
+/// text, `end` is where the text ends so far, excluding trailing spaces.
+#[derive(Debug, Copy, Clone)]
+struct Resume {
+    start: Position,
+    cursor: Cursor,
+    end: Position,
+}
+
+/// The outcome of reading a token.
+enum Step {
+    Token(Lexeme),
+    Error(ParseError),
+    End,
+    /// The source ran out before the token was complete. The reader is left
+    /// as it was before the attempt so it can be retried with more input.
+    NeedMore,
+}
+
+/// The size of the initial window over bytes given to
+/// [`JsonTextReader::from_bytes`] that is validated as UTF-8.
+const WINDOW_SIZE: usize = 64 * 1024;
+
 #[derive(Debug)]
 pub struct JsonTextReader<'s> {
-    source: &'s str,
+    source: Cow<'s, str>,
+    base: usize,
+    /// The input when reading from bytes, of which `source` is the window
+    /// validated so far, starting at `base`, and otherwise empty.
+    bytes: &'s [u8],
+    /// Where the token being read starts in `bytes`.
+    anchor: usize,
+    encoding: Encoding,
+    tail: Tail,
+    exhausted: Option<Position>,
+    /// Where reading reached [`ReaderOptions::max_input_length`].
+    overrun: Option<Position>,
+    resume: Option<Resume>,
+    options: ReaderOptions,
     state_stack: Vec<ReaderState>,
-    idx_chars: std::str::CharIndices<'s>,
-    next: Option<IdxChar>,
+    containers: Vec<Container>,
+    depth: usize,
+    /// Tokens read but not yet returned, along with their depth, which are
+    /// the trivia skipped before a token followed by the token itself.
+    pending: VecDeque<(Lexeme, usize)>,
+    path: Vec<PathSegment>,
+    cursor: Cursor,
+    mark: Cursor,
 }
 
 impl<'s> Iterator for JsonTextReader<'s> {
-    type Item = Result<JsonToken<'s>, SyntaxError>;
+    type Item = Result<JsonToken<'s>, ParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.state_stack.pop().map(|state| match state {
-            ReaderState::Parse => self.parse(),
-            ReaderState::ParseArrayFirst => self.parse_array_first(),
-            ReaderState::ParseArrayNext => self.parse_array_next(),
-            ReaderState::ParseObjectMemberName => self.parse_object_member_name(),
-            ReaderState::ParseObjectMemberValue => self.parse_object_member_value(),
-            ReaderState::ParseObjectMemberNext => self.parse_object_member_next(),
-        })
+        match self.read() {
+            Step::Token(lexeme) => Some(Ok(self.token(lexeme))),
+            Step::Error(err) => Some(Err(err)),
+            Step::End => None,
+            Step::NeedMore => unreachable!("a whole source never needs more input"),
+        }
     }
 }
 
 impl<'s> JsonTextReader<'s> {
     pub fn new(source: &'s str) -> Self {
+        Self::with_options(source, ReaderOptions::default())
+    }
+
+    pub fn with_options(source: &'s str, options: ReaderOptions) -> Self {
+        Self::with_source(Cow::Borrowed(source), Tail::End, options)
+    }
+
+    /// Reads from bytes in the encoding detected by [`Encoding::detect`].
+    /// UTF-8 is validated as reading progresses, so that an invalid sequence
+    /// is reported as [`SyntaxError::InvalidUtf8`] once reading reaches it,
+    /// after the tokens that precede it. UTF-16 and UTF-32 are transcoded to
+    /// UTF-8 up front, and positions are then relative to the transcoded
+    /// text. A sequence that cannot be transcoded is reported as
+    /// [`SyntaxError::InvalidEncoding`] once reading reaches it.
+    pub fn from_bytes(bytes: &'s [u8]) -> Self {
+        Self::from_bytes_with_options(bytes, ReaderOptions::default())
+    }
+
+    pub fn from_bytes_with_options(bytes: &'s [u8], options: ReaderOptions) -> Self {
+        let encoding = Encoding::detect(bytes);
+        let mut reader = if encoding == Encoding::Utf8 {
+            let tail = if bytes.is_empty() { Tail::End } else { Tail::More };
+            let mut reader = Self::with_source(Cow::Borrowed(""), tail, options);
+            reader.bytes = bytes;
+            reader
+        } else {
+            let mut text = String::with_capacity(bytes.len());
+            let (decoded, _) = encoding.decode(bytes, &mut text);
+            let tail = if decoded == bytes.len() { Tail::End } else { Tail::Invalid };
+            Self::with_source(Cow::Owned(text), tail, options)
+        };
+        reader.encoding = encoding;
+        reader
+    }
+
+    fn with_source(source: Cow<'s, str>, tail: Tail, options: ReaderOptions) -> Self {
+        let mut state_stack = vec![ReaderState::Parse];
+        if !options.trailing_content {
+            state_stack.insert(0, ReaderState::End);
+        } else if options.trivia || options.comment_tokens {
+            state_stack.insert(0, ReaderState::Trivia);
+        }
         Self {
             source,
-            idx_chars: source.char_indices(),
-            next: None,
-            state_stack: vec![ReaderState::Parse],
+            base: 0,
+            bytes: &[],
+            anchor: 0,
+            encoding: Encoding::Utf8,
+            tail,
+            exhausted: None,
+            overrun: None,
+            resume: None,
+            options,
+            cursor: Cursor::default(),
+            mark: Cursor::default(),
+            state_stack,
+            containers: Vec::new(),
+            depth: 0,
+            pending: VecDeque::new(),
+            path: Vec::new(),
+        }
+    }
+
+    pub fn options(&self) -> &ReaderOptions {
+        &self.options
+    }
+
+    /// The encoding of the source, which is always UTF-8 unless reading
+    /// from bytes.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// The number of arrays and objects that enclose the last token read,
+    /// where the start and end of an array or object are not considered
+    /// to be enclosed by it.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The JSON Pointer (RFC 6901) to the value that the last token read is
+    /// part of, like `/servers/3/port`, where the name of a member points to
+    /// its value, and the start and end of an array or object point to it.
+    /// Comments and whitespace leave it as it was. The pointer to the value
+    /// at the top level is empty.
+    pub fn current_pointer(&self) -> String {
+        let mut current = String::new();
+        for segment in &self.path {
+            match segment {
+                PathSegment::Index(Some(index)) => pointer::push(&mut current, &index.to_string()),
+                PathSegment::Name(Some(name)) => pointer::push(&mut current, name),
+                _ => {}
+            }
+        }
+        current
+    }
+
+    /// The position just past the last character consumed by the reader.
+    pub fn position(&self) -> Position {
+        self.cursor.position
+    }
+
+    fn read(&mut self) -> Step {
+        if let Some(pending) = self.pending.pop_front() {
+            return self.emit(pending);
+        }
+        let Some(state) = self.state_stack.pop() else {
+            return Step::End;
+        };
+        let (depth, cursor, mark) = (self.state_stack.len(), self.cursor, self.mark);
+        self.exhausted = None;
+        self.anchor = cursor.position.offset;
+        let mut result = match state {
+            ReaderState::End => self.parse_end().map(|()| None),
+            ReaderState::Trivia => self.parse_trivia().map(|()| None),
+            state => self.parse_state(state).map(Some),
+        };
+        if let Some(position) = self.overrun.take() {
+            self.mark.position = position;
+            result = Err(SyntaxError::InputLimitExceeded);
+        } else if let Some(position) = self.exhausted {
+            if self.tail == Tail::More {
+                self.state_stack.truncate(depth);
+                self.state_stack.push(state);
+                self.pending.clear();
+                self.cursor = cursor;
+                self.mark = mark;
+                return Step::NeedMore;
+            }
+            self.mark.position = position;
+            result = Err(match self.encoding {
+                Encoding::Utf8 => SyntaxError::InvalidUtf8,
+                _ => SyntaxError::InvalidEncoding,
+            });
+        }
+        self.resume = None;
+        if let Ok(Some(lexeme)) = result {
+            if let Err(kind) = self.count(state, lexeme) {
+                result = Err(kind);
+            }
+        }
+        match result {
+            Ok(result) => {
+                if let Some(lexeme) = result {
+                    let depth = match lexeme.0 {
+                        JsonTokenKind::ArrayStart | JsonTokenKind::ObjectStart => {
+                            self.containers.push(Container::default());
+                            self.containers.len() - 1
+                        }
+                        JsonTokenKind::ArrayEnd | JsonTokenKind::ObjectEnd => {
+                            self.containers.pop();
+                            self.containers.len()
+                        }
+                        _ => self.containers.len(),
+                    };
+                    self.pending.push_back((lexeme, depth));
+                }
+                match self.pending.pop_front() {
+                    Some(pending) => self.emit(pending),
+                    None => Step::End,
+                }
+            }
+            Err(kind) => {
+                //
+                // There is no recovering from a syntax error so drop any
+                // pending states and have the reading end after reporting it.
+                //
+                self.state_stack.clear();
+                self.pending.clear();
+                Step::Error(ParseError::new(kind, self.mark.position))
+            }
+        }
+    }
+
+    /// Returns a token that was read, keeping track of where it is.
+    fn emit(&mut self, (lexeme, depth): (Lexeme, usize)) -> Step {
+        self.depth = depth;
+        match lexeme.0 {
+            JsonTokenKind::Comment | JsonTokenKind::Whitespace => {}
+            JsonTokenKind::ArrayEnd | JsonTokenKind::ObjectEnd => {
+                self.path.pop();
+            }
+            JsonTokenKind::ObjectMember => {
+                let token = self.token(lexeme);
+                let name = match token.as_str() {
+                    Ok(name) => name,
+                    Err(_) => token.into_text(),
+                };
+                if let Some(PathSegment::Name(last)) = self.path.last_mut() {
+                    *last = Some(name.into_owned());
+                }
+            }
+            kind => {
+                if let Some(PathSegment::Index(last)) = self.path.last_mut() {
+                    *last = Some(last.map_or(0, |index| index + 1));
+                }
+                match kind {
+                    JsonTokenKind::ArrayStart => self.path.push(PathSegment::Index(None)),
+                    JsonTokenKind::ObjectStart => self.path.push(PathSegment::Name(None)),
+                    _ => {}
+                }
+            }
+        }
+        Step::Token(lexeme)
+    }
+
+    /// Counts an element or member, which is any token read in an array or
+    /// the name of a member, except for the end, against the limit and, if
+    /// duplicates are an error, checks the name of a member.
+    fn count(&mut self, state: ReaderState, (kind, span): Lexeme) -> Result<(), SyntaxError> {
+        let counted = match state {
+            ReaderState::ParseArrayFirst | ReaderState::ParseArrayNext => {
+                kind != JsonTokenKind::ArrayEnd
+            }
+            ReaderState::ParseObjectMemberName | ReaderState::ParseObjectMemberNext => {
+                kind == JsonTokenKind::ObjectMember
+            }
+            _ => false,
+        };
+        if !counted {
+            return Ok(());
+        }
+        let name = match self.options.duplicate_keys {
+            DuplicateKeys::Error if kind == JsonTokenKind::ObjectMember => {
+                match self.token((kind, span)).as_str() {
+                    Ok(name) => Some(name.into_owned()),
+                    Err(err) => {
+                        self.mark.position = err.position();
+                        return Err(err.kind());
+                    }
+                }
+            }
+            _ => None,
+        };
+        let container = self.containers.last_mut().expect("an open array or object");
+        if container.length == self.options.max_members {
+            self.mark.position = span.start;
+            return Err(SyntaxError::MemberLimitExceeded);
+        }
+        if let Some(name) = name {
+            if !container.names.insert(name) {
+                self.mark.position = span.start;
+                return Err(SyntaxError::DuplicateKey);
+            }
+        }
+        container.length += 1;
+        Ok(())
+    }
+
+    fn parse_state(&mut self, state: ReaderState) -> Result<Lexeme, SyntaxError> {
+        match state {
+            ReaderState::End | ReaderState::Trivia => unreachable!(),
+            ReaderState::Parse => self.parse(),
+            ReaderState::ParseArrayFirst => self.parse_array_first(),
+            ReaderState::ParseArrayNext => self.parse_array_next(),
+            ReaderState::ParseObjectMemberName => self.parse_object_member_name(),
+            ReaderState::ParseObjectMemberValue => self.parse_object_member_value(),
+            ReaderState::ParseObjectMemberNext => self.parse_object_member_next(),
         }
     }
 
     fn next(&mut self) -> Option<IdxChar> {
-        if let Some(next) = self.next {
-            self.next = None;
-            Some(next)
-        } else {
-            self.idx_chars.next()
+        self.mark = self.cursor;
+        let position = self.cursor.position;
+        let ch = loop {
+            match self.source[position.offset - self.base..].chars().next() {
+                Some(ch) => break ch,
+                None if self.extend() => {}
+                None => {
+                    if self.tail != Tail::End && self.exhausted.is_none() {
+                        self.exhausted = Some(position);
+                    }
+                    return None;
+                }
+            }
+        };
+        if position.offset + ch.len_utf8() > self.options.max_input_length {
+            self.overrun.get_or_insert(position);
+            return None;
+        }
+        self.cursor.advance(ch);
+        Some((position, ch))
+    }
+
+    /// Grows the window over the bytes being read, from the start of the
+    /// token being read to at least twice its current length, so validating
+    /// the part that is validated again is amortized. Returns whether the
+    /// window grew.
+    fn extend(&mut self) -> bool {
+        if self.bytes.is_empty() || self.tail != Tail::More {
+            return false;
+        }
+        let start = self.anchor;
+        let end = self.base + self.source.len();
+        let stop = self.bytes.len().min(end + (end - start).max(WINDOW_SIZE));
+        let (valid, tail) = match std::str::from_utf8(&self.bytes[start..stop]) {
+            Ok(_) if stop == self.bytes.len() => (stop, Tail::End),
+            Ok(_) => (stop, Tail::More),
+            Err(err) if err.error_len().is_none() && stop < self.bytes.len() => {
+                (start + err.valid_up_to(), Tail::More)
+            }
+            Err(err) => (start + err.valid_up_to(), Tail::Invalid),
+        };
+        let text = std::str::from_utf8(&self.bytes[start..valid]).expect("valid UTF-8");
+        self.source = Cow::Borrowed(text);
+        self.base = start;
+        self.tail = tail;
+        valid > end
+    }
+
+    /// In lossy mode, steps over the invalid UTF-8 sequence at the end of the
+    /// window as if it were a single character and starts a new window after
+    /// it. Returns whether it did.
+    fn skip_invalid(&mut self) -> bool {
+        if !self.options.lossy_utf8 || self.bytes.is_empty() || self.tail != Tail::Invalid {
+            return false;
+        }
+        let offset = self.cursor.position.offset;
+        let len = match std::str::from_utf8(&self.bytes[offset..]) {
+            Err(err) => err.error_len().unwrap_or(self.bytes.len() - offset),
+            Ok(_) => unreachable!("the window ends before an invalid sequence"),
+        };
+        self.exhausted = None;
+        self.cursor.advance('\u{FFFD}');
+        self.cursor.position.offset = offset + len;
+        self.mark = self.cursor;
+        self.anchor = self.cursor.position.offset;
+        self.base = self.anchor;
+        self.source = Cow::Borrowed("");
+        self.tail = if self.anchor < self.bytes.len() { Tail::More } else { Tail::End };
+        true
+    }
+
+    fn checkpoint(&mut self, start: Position, end: Position) {
+        self.resume = Some(Resume { start, cursor: self.cursor, end });
+    }
+
+    /// Skips over the part of the construct starting at `start` that was
+    /// scanned by an earlier attempt, if any, returning the recorded end.
+    fn resume(&mut self, start: Position) -> Option<Position> {
+        let resume = self.resume.filter(|resume| resume.start == start)?;
+        self.cursor = resume.cursor;
+        Some(resume.end)
+    }
+
+    /// The offset in the source before which nothing more will be read or
+    /// returned, which is the start of the first pending token, if any.
+    fn consumed(&self) -> usize {
+        match self.pending.front() {
+            Some(((_, span), _)) => span.start.offset,
+            None => self.cursor.position.offset,
         }
     }
 
-    fn back(&mut self, ich: IdxChar) {
-        self.next = Some(ich)
+    /// Un-reads the last character returned by `next`.
+    fn back(&mut self) {
+        self.cursor = self.mark
     }
 
-    fn parse_object_member_name(&mut self) -> Result<JsonToken<'s>, SyntaxError> {
+    fn range(&self, span: Span) -> Range<usize> {
+        span.start.offset - self.base..span.end.offset - self.base
+    }
+
+    fn token(&self, (kind, span): Lexeme) -> JsonToken<'s> {
+        if span.start.offset < self.base {
+            //
+            // The window only moves past the start of a token when an invalid
+            // sequence was skipped inside it.
+            //
+            let bytes = &self.bytes[span.start.offset..span.end.offset];
+            return JsonToken::new(kind, String::from_utf8_lossy(bytes), span);
+        }
+        let range = self.range(span);
+        let text = match &self.source {
+            Cow::Borrowed(source) => Cow::Borrowed(&source[range]),
+            Cow::Owned(source) => Cow::Owned(source[range].to_owned()),
+        };
+        JsonToken::new(kind, text, span)
+    }
+
+    /// Returns a token whose text is borrowed from the source held by the
+    /// reader, even if the reader owns it.
+    fn borrow_token(&self, (kind, span): Lexeme) -> JsonToken<'_> {
+        JsonToken::new(kind, Cow::Borrowed(&self.source[self.range(span)]), span)
+    }
+
+    fn lexeme(&self, kind: JsonTokenKind, start: Position, end: Position) -> Lexeme {
+        (kind, Span { start, end })
+    }
+
+    fn punctuator(&self, kind: JsonTokenKind, start: Position) -> Lexeme {
+        self.lexeme(kind, start, self.cursor.position)
+    }
+
+    fn parse_end(&mut self) -> Result<(), SyntaxError> {
+        match self.next_clean()? {
+            Some(_) => Err(SyntaxError::TrailingContent),
+            None => Ok(()),
+        }
+    }
+
+    fn parse_trivia(&mut self) -> Result<(), SyntaxError> {
+        if self.next_clean()?.is_some() {
+            self.back();
+        }
+        Ok(())
+    }
+
+    fn parse_object_member_name(&mut self) -> Result<Lexeme, SyntaxError> {
         let ich = self.next_clean()?.ok_or(SyntaxError::UnterminatedObject)?;
         if let (i, '}') = ich {
-            Ok(JsonToken::new(JsonTokenKind::ObjectEnd, &self.source[i..=i]))
+            Ok(self.punctuator(JsonTokenKind::ObjectEnd, i))
         } else {
-            self.back(ich);
-            self.state_stack.push(ReaderState::ParseObjectMemberValue);
-            self.parse().map(|token| JsonToken::new(JsonTokenKind::ObjectMember, token.text()))
+            self.back();
+            self.parse_object_member()
         }
     }
 
-    fn parse_object_member_value(&mut self) -> Result<JsonToken<'s>, SyntaxError> {
+    fn parse_object_member(&mut self) -> Result<Lexeme, SyntaxError> {
+        //
+        // A member name can be any string or, if accepted, unquoted text,
+        // which includes numbers and literals, but never an array or object.
+        //
+        if let Some((_, '{' | '[')) = self.next_clean()? {
+            Err(SyntaxError::InvalidMemberName)?;
+        }
+        self.back();
+        self.state_stack.push(ReaderState::ParseObjectMemberValue);
+        let (kind, span) = self.parse()?;
+        if kind != JsonTokenKind::String && !self.options.unquoted_strings {
+            self.mark.position = span.start;
+            Err(SyntaxError::InvalidMemberName)?;
+        }
+        Ok((JsonTokenKind::ObjectMember, span))
+    }
+
+    fn parse_object_member_value(&mut self) -> Result<Lexeme, SyntaxError> {
         let ich = self.next_clean()?.ok_or(SyntaxError::UnterminatedObject)?;
         match ich {
             (_, ':') => {}
-            (_, '=') => {
-                let ich = self.next().ok_or(SyntaxError::InvalidMemberValueDelimiter)?;
-                if let (_, '>') = ich {
-                    self.back(ich);
+            (_, '=') if self.options.assignment_delimiters => {
+                let (_, ch) = self.next().ok_or(SyntaxError::InvalidMemberValueDelimiter)?;
+                if ch != '>' {
+                    self.back();
                 }
             }
             _ => Err(SyntaxError::InvalidMemberValueDelimiter)?,
@@ -143,114 +1238,192 @@ impl<'s> JsonTextReader<'s> {
         self.parse()
     }
 
-    fn parse_object_member_next(&mut self) -> Result<JsonToken<'s>, SyntaxError> {
+    fn parse_object_member_next(&mut self) -> Result<Lexeme, SyntaxError> {
         let ich = self.next_clean()?.ok_or(SyntaxError::UnterminatedObject)?;
         match ich {
-            (_, ';' | ',') => {
+            (_, ch @ (',' | ';')) if ch == ',' || self.options.semicolon_separators => {
                 let ich = self.next_clean()?.ok_or(SyntaxError::UnterminatedObject)?;
                 if let (i, '}') = ich {
-                    Ok(JsonToken::new(JsonTokenKind::ObjectEnd, &self.source[i..=i]))
+                    if !self.options.trailing_commas {
+                        Err(SyntaxError::TrailingComma)?;
+                    }
+                    Ok(self.punctuator(JsonTokenKind::ObjectEnd, i))
                 } else {
-                    self.back(ich);
-                    self.state_stack.push(ReaderState::ParseObjectMemberValue);
-                    self.parse()
+                    self.back();
+                    self.parse_object_member()
                 }
             }
-            (i, '}') => Ok(JsonToken::new(JsonTokenKind::ObjectEnd, &self.source[i..=i])),
+            (i, '}') => Ok(self.punctuator(JsonTokenKind::ObjectEnd, i)),
             _ => Err(SyntaxError::UnterminatedObject),
         }
     }
 
-    fn parse_array_first(&mut self) -> Result<JsonToken<'s>, SyntaxError> {
+    fn parse_array_first(&mut self) -> Result<Lexeme, SyntaxError> {
         let ich = self.next_clean()?.ok_or(SyntaxError::UnterminatedArray)?;
         if let (i, ']') = ich {
-            Ok(JsonToken::new(JsonTokenKind::ArrayEnd, &self.source[i..=i]))
+            Ok(self.punctuator(JsonTokenKind::ArrayEnd, i))
         } else {
-            self.back(ich);
+            self.back();
             self.state_stack.push(ReaderState::ParseArrayNext);
             self.parse()
         }
     }
 
-    fn parse_array_next(&mut self) -> Result<JsonToken<'s>, SyntaxError> {
+    fn parse_array_next(&mut self) -> Result<Lexeme, SyntaxError> {
         let ich = self.next_clean()?.ok_or(SyntaxError::UnterminatedArray)?;
         match ich {
-            (_, ',' | ';') => {
+            (_, ch @ (',' | ';')) if ch == ',' || self.options.semicolon_separators => {
                 let ich = self.next_clean()?.ok_or(SyntaxError::UnterminatedArray)?;
                 if let (i, ']') = ich {
-                    Ok(JsonToken::new(JsonTokenKind::ArrayEnd, &self.source[i..=i]))
+                    if !self.options.trailing_commas {
+                        Err(SyntaxError::TrailingComma)?;
+                    }
+                    Ok(self.punctuator(JsonTokenKind::ArrayEnd, i))
                 } else {
-                    self.back(ich);
+                    self.back();
                     self.state_stack.push(ReaderState::ParseArrayNext);
                     self.parse()
                 }
             }
-            (i, ']') => Ok(JsonToken::new(JsonTokenKind::ArrayEnd, &self.source[i..=i])),
+            (i, ']') => Ok(self.punctuator(JsonTokenKind::ArrayEnd, i)),
             _ => Err(SyntaxError::UnterminatedArray),
         }
     }
 
-    fn parse_string(&mut self, quote: IdxChar) -> Result<&'s str, SyntaxError> {
+    fn parse_string(&mut self, quote: IdxChar) -> Result<Lexeme, SyntaxError> {
         let (si, quote) = quote;
+        let strict = !self.options.lenient_strings;
+        self.resume(si);
         loop {
-            match self.next() {
+            self.checkpoint(si, si);
+            let next = self.next();
+            if next.is_some()
+                && self.cursor.position.offset - si.offset > self.options.max_string_length
+            {
+                self.mark.position = si;
+                Err(SyntaxError::StringLimitExceeded)?;
+            }
+            match next {
+                None if self.skip_invalid() => {}
                 None | Some((_, '\n')) | Some((_, '\r')) => Err(SyntaxError::UnterminatedString)?,
-                Some((_, '\\')) if self.next().is_none() => Err(SyntaxError::UnterminatedString)?,
-                Some((ei, ch)) if ch == quote => return Ok(&self.source[si..=ei]),
+                Some((_, '\\')) => {
+                    let (_, ch) = self.next().ok_or(SyntaxError::UnterminatedString)?;
+                    if strict {
+                        self.check_escape(quote, ch)?;
+                    }
+                }
+                Some((_, ch)) if ch == quote => {
+                    return Ok(self.punctuator(JsonTokenKind::String, si));
+                }
+                Some((_, ch)) if strict && ch < ' ' => Err(SyntaxError::ControlCharacter)?,
                 _ => {}
             }
         }
     }
 
-    fn parse(&mut self) -> Result<JsonToken<'s>, SyntaxError> {
+    fn check_escape(&mut self, quote: char, ch: char) -> Result<(), SyntaxError> {
+        match ch {
+            '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' => Ok(()),
+            '\'' if quote == '\'' => Ok(()),
+            'u' => {
+                for _ in 0..4 {
+                    match self.next() {
+                        Some((_, ch)) if ch.is_ascii_hexdigit() => {}
+                        Some(_) => Err(SyntaxError::InvalidEscape)?,
+                        None => Err(SyntaxError::UnterminatedString)?,
+                    }
+                }
+                Ok(())
+            }
+            _ => Err(SyntaxError::InvalidEscape),
+        }
+    }
+
+    fn parse(&mut self) -> Result<Lexeme, SyntaxError> {
         let ich = self.next_clean()?.ok_or(SyntaxError::MissingValue)?;
         Ok(match ich {
             // String
-            ich @ (_, ch) if ch == '"' || ch == '\'' => {
-                JsonToken::new(JsonTokenKind::String, self.parse_string(ich)?)
+            ich @ (_, '"') => self.parse_string(ich)?,
+            ich @ (_, '\'') if self.options.single_quoted_strings => self.parse_string(ich)?,
+            (_, '\'') => Err(SyntaxError::SingleQuotedString)?,
+            (_, '{' | '[') if self.containers.len() >= self.options.max_depth => {
+                Err(SyntaxError::DepthLimitExceeded)?
             }
             (i, '{') => {
                 self.state_stack.push(ReaderState::ParseObjectMemberName);
-                JsonToken::new(JsonTokenKind::ObjectStart, &self.source[i..=i])
+                self.punctuator(JsonTokenKind::ObjectStart, i)
             }
             (i, '[') => {
                 self.state_stack.push(ReaderState::ParseArrayFirst);
-                JsonToken::new(JsonTokenKind::ArrayStart, &self.source[i..=i])
+                self.punctuator(JsonTokenKind::ArrayStart, i)
             }
-            (si, mut ch) => {
+            ich @ (si, _) => {
                 //
                 // Handle unquoted text. This could be the values true, false, or
                 // null, or it can be a number. An implementation (such as this one)
                 // is allowed to also accept non-standard forms.
                 //
                 // Accumulate characters until we reach the end of the text or a
-                // formatting character.
+                // formatting character. Trailing spaces are not part of the text.
+                //
+                // Stop short of scanning text that is too long for both a
+                // number and a string.
                 //
+                let limit = match self.options.unquoted_strings {
+                    true => self.options.max_number_length.max(self.options.max_string_length),
+                    false => self.options.max_number_length,
+                };
                 let mut ei = si;
-                let mut pi = si;
-                loop {
-                    if ch >= ' ' && ",:]}/\\\"[{;=#".find(ch).is_none() {
-                        ei += ch.len_utf8();
-                        if let Some(next) = self.next() {
-                            (pi, ch) = next;
-                        } else {
+                let mut next = Some(ich);
+                if let Some(end) = self.resume(si) {
+                    ei = end;
+                    next = self.next();
+                }
+                while let Some((_, ch)) = next {
+                    //
+                    // Only unquoted strings can have spaces inside, so without
+                    // them a space ends the text, as in `1 2`.
+                    //
+                    if ch < ' '
+                        || ch == ' ' && !self.options.unquoted_strings
+                        || ",:]}/\\\"[{;=#".contains(ch)
+                    {
+                        self.back();
+                        break;
+                    }
+                    if ch != ' ' {
+                        ei = self.cursor.position;
+                        if ei.offset - si.offset > limit {
                             break;
                         }
-                    } else {
-                        self.back((pi, ch));
-                        break;
                     }
+                    self.checkpoint(si, ei);
+                    next = self.next();
                 }
 
                 if ei == si {
                     Err(SyntaxError::MissingValue)?;
                 }
 
-                let tt = &self.source[si..ei];
+                //
+                // Leave any trailing spaces to be skipped as whitespace.
+                //
+                self.cursor = Cursor { position: ei, after_cr: false };
+
+                let tt = &self.source[si.offset - self.base..ei.offset - self.base];
                 let kind = match tt {
                     "null" => JsonTokenKind::Null,
                     "true" => JsonTokenKind::True,
                     "false" => JsonTokenKind::False,
+                    other
+                        if other.len() > self.options.max_number_length
+                            && other.starts_with(|ch: char| {
+                                ch.is_ascii_digit() || "+-.".contains(ch)
+                            }) =>
+                    {
+                        self.mark.position = si;
+                        Err(SyntaxError::NumberLimitExceeded)?
+                    }
                     other => {
                         //
                         // Try converting it. We support the 0- and 0x- conventions.
@@ -258,72 +1431,628 @@ impl<'s> JsonTextReader<'s> {
                         // be a string. Note that the 0-, 0x-, plus, and implied
                         // string conventions are non-standard, but a JSON text parser
                         // is free to accept non-JSON text forms as long as it accepts
-                        // all correct JSON text forms.
+                        // all correct JSON text forms. Text too long to be a number
+                        // can only be a string.
                         //
-                        if other.trim_end().parse::<f64>().is_ok() {
+                        let short = other.len() <= self.options.max_number_length;
+                        if short
+                            && (self.options.lenient_numbers && number::is_lenient_number(other)
+                                || is_number(other))
+                        {
                             JsonTokenKind::Number
-                        } else {
+                        } else if self.options.unquoted_strings {
+                            if other.len() > self.options.max_string_length {
+                                self.mark.position = si;
+                                Err(SyntaxError::StringLimitExceeded)?;
+                            }
                             JsonTokenKind::String
+                        } else {
+                            self.mark.position = si;
+                            if short && other.parse::<f64>().is_ok() {
+                                Err(SyntaxError::InvalidNumber)?
+                            } else {
+                                Err(SyntaxError::UnquotedString)?
+                            }
                         }
                     }
                 };
 
-                JsonToken::new(kind, tt)
+                self.lexeme(kind, si, ei)
             }
         })
     }
 
+    fn skip_line_comment(&mut self, start: Position) {
+        self.resume(start);
+        loop {
+            self.checkpoint(start, start);
+            match self.next() {
+                None => break,
+                Some((_, '\n' | '\r')) => {
+                    self.back();
+                    break;
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn skip_block_comment(&mut self, start: Position) -> Result<(), SyntaxError> {
+        self.resume(start);
+        loop {
+            self.checkpoint(start, start);
+            let Some((_, ch)) = self.next() else {
+                return Err(SyntaxError::UnclosedComment);
+            };
+
+            if ch == '*' {
+                match self.next() {
+                    Some((_, '/')) => return Ok(()),
+                    Some(_) => self.back(),
+                    _ => return Err(SyntaxError::UnclosedComment),
+                };
+            }
+        }
+    }
+
     fn next_clean(&mut self) -> Result<Option<IdxChar>, SyntaxError> {
+        //
+        // Keep track of where the run of whitespace being skipped started
+        // for trivia mode.
+        //
+        let mut space = self.cursor.position;
         loop {
-            let Some(ich @ (_, ch)) = self.next() else {
+            let Some(ich @ (i, ch)) = self.next() else {
+                self.skipped(JsonTokenKind::Whitespace, space, self.cursor.position);
                 return Ok(None)
             };
             match ch {
                 '/' => {
+                    let slash = self.mark;
                     match self.next() {
-                        None => return Ok(Some(ich)),
+                        Some((_, '/' | '*')) if !self.options.comments => {
+                            self.mark = slash;
+                            return Err(SyntaxError::UnexpectedComment);
+                        }
+                        None => {
+                            self.mark = slash;
+                            self.skipped(JsonTokenKind::Whitespace, space, i);
+                            return Ok(Some(ich));
+                        }
                         //
                         // Single-line comment: // ...
                         //
-                        Some((_, '/')) => {
-                            while let Some((_, ch)) = self.next() {
-                                if let '\n' | '\r' = ch {
-                                    break;
-                                }
-                            }
-                        }
+                        Some((_, '/')) => self.skip_line_comment(i),
                         //
                         // Multi-line comment: /* ... */
                         //
-                        Some((_, '*')) => loop {
-                            let Some((_, ch)) = self.next() else {
-                                return Err(SyntaxError::UnclosedComment);
-                            };
-
-                            if ch == '*' {
-                                match self.next() {
-                                    Some((_, '/')) => break,
-                                    Some(ich) => self.back(ich),
-                                    _ => return Err(SyntaxError::UnclosedComment),
-                                };
-                            }
-                        },
-                        Some(ich) => {
-                            self.back(ich);
+                        Some((_, '*')) => self.skip_block_comment(i)?,
+                        Some(_) => {
+                            self.back();
+                            self.mark = slash;
+                            self.skipped(JsonTokenKind::Whitespace, space, i);
                             return Ok(Some(ich));
                         }
                     }
                 }
-                '#' => {
-                    while let Some((_, ch)) = self.next() {
-                        if ch == '\n' || ch == '\r' {
-                            break;
-                        }
-                    }
+                '#' if !self.options.hash_comments => return Err(SyntaxError::UnexpectedComment),
+                '#' => self.skip_line_comment(i),
+                //
+                // A byte order mark is only allowed at the very start.
+                //
+                '\u{FEFF}' if i.offset == 0 => continue,
+                ch if ch > ' ' => {
+                    self.skipped(JsonTokenKind::Whitespace, space, i);
+                    return Ok(Some(ich));
                 }
-                ch if ch > ' ' => return Ok(Some(ich)),
-                _ => continue,
+                ' ' | '\t' | '\n' | '\r' => continue,
+                _ if self.options.lenient_whitespace => continue,
+                _ => return Err(SyntaxError::UnexpectedCharacter),
             }
+            //
+            // Only comments get this far.
+            //
+            self.skipped(JsonTokenKind::Whitespace, space, i);
+            self.skipped(JsonTokenKind::Comment, i, self.cursor.position);
+            space = self.cursor.position;
+        }
+    }
+
+    /// Queues a comment or run of whitespace that was skipped, if read as a
+    /// token, to be returned before the token that follows it.
+    fn skipped(&mut self, kind: JsonTokenKind, start: Position, end: Position) {
+        let read = match kind {
+            JsonTokenKind::Comment => self.options.comment_tokens || self.options.trivia,
+            _ => self.options.trivia,
+        };
+        if read && start.offset < end.offset {
+            let span = Span { start, end };
+            self.pending.push_back(((kind, span), self.containers.len()));
+        }
+    }
+}
+
+/// Determines whether the text is a number as specified by RFC 8259:
+///
+/// ```text
+/// number = [ minus ] int [ frac ] [ exp ]
+/// ```
+fn is_number(text: &str) -> bool {
+    fn digits(s: &[u8]) -> usize {
+        s.iter().take_while(|b| b.is_ascii_digit()).count()
+    }
+
+    let mut s = text.as_bytes();
+    if let [b'-', rest @ ..] = s {
+        s = rest;
+    }
+    s = match s {
+        [b'0', rest @ ..] => rest,
+        [b'1'..=b'9', ..] => &s[digits(s)..],
+        _ => return false,
+    };
+    if let [b'.', rest @ ..] = s {
+        match digits(rest) {
+            0 => return false,
+            n => s = &rest[n..],
+        }
+    }
+    if let [b'e' | b'E', rest @ ..] = s {
+        let rest = match rest {
+            [b'+' | b'-', rest @ ..] => rest,
+            _ => rest,
+        };
+        match digits(rest) {
+            0 => return false,
+            n => s = &rest[n..],
+        }
+    }
+    s.is_empty()
+}
+
+/// Decodes the escape sequences in the content of a quoted string. On error,
+/// returns the byte index of the offending escape sequence.
+fn unescape(s: &str) -> Result<String, (usize, SyntaxError)> {
+    let mut result = String::with_capacity(s.len());
+    unescape_with(s, |ch| result.push(ch))?;
+    Ok(result)
+}
+
+/// Decodes the escape sequences in the text of a string, without its
+/// quotes, passing each character to `push`. On error, returns the offset
+/// of the offending escape sequence.
+fn unescape_with(s: &str, mut push: impl FnMut(char)) -> Result<(), (usize, SyntaxError)> {
+    fn hex4(s: &str, i: usize) -> Result<u16, (usize, SyntaxError)> {
+        s.get(i + 2..i + 6)
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+            .ok_or((i, SyntaxError::InvalidEscape))
+    }
+
+    let mut ich = s.char_indices();
+    while let Some((i, ch)) = ich.next() {
+        if ch != '\\' {
+            push(ch);
+            continue;
+        }
+        let Some((_, ch)) = ich.next() else {
+            return Err((i, SyntaxError::InvalidEscape));
+        };
+        push(match ch {
+            'b' => '\x08',
+            'f' => '\x0C',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let hi = hex4(s, i)?;
+                ich.nth(3);
+                let cp = match hi {
+                    0xD800..=0xDBFF => {
+                        //
+                        // A high surrogate must be immediately followed by an
+                        // escaped low surrogate to make a pair.
+                        //
+                        let j = i + 6;
+                        let lo = match s[j..].starts_with("\\u").then(|| hex4(s, j)) {
+                            Some(Ok(lo @ 0xDC00..=0xDFFF)) => lo,
+                            Some(Err(err)) => return Err(err),
+                            _ => return Err((i, SyntaxError::LoneSurrogate)),
+                        };
+                        ich.nth(5);
+                        0x10000 + ((u32::from(hi) - 0xD800) << 10) + (u32::from(lo) - 0xDC00)
+                    }
+                    0xDC00..=0xDFFF => return Err((i, SyntaxError::LoneSurrogate)),
+                    cp => u32::from(cp),
+                };
+                char::from_u32(cp).expect("code point is not a surrogate")
+            }
+            ch => ch,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(text: &str) -> Vec<(usize, usize, usize, usize)> {
+        JsonTextReader::new(text)
+            .map(|token| {
+                let span = token.unwrap().span();
+                let (start, end) = (span.start(), span.end());
+                (start.offset(), start.line(), start.column(), end.offset())
+            })
+            .collect()
+    }
+
+    fn error(text: &str) -> ParseError {
+        JsonTextReader::new(text).collect::<Result<Vec<_>, _>>().unwrap_err()
+    }
+
+    #[test]
+    fn tokens_have_positions() {
+        assert_eq!(
+            spans("[\"\u{e9}\",\r\n\t{\"a\":\n1}]"),
+            [
+                (0, 1, 1, 1),
+                (1, 1, 2, 5),
+                (9, 2, 2, 10),
+                (10, 2, 3, 13),
+                (15, 3, 1, 16),
+                (16, 3, 2, 17),
+                (17, 3, 3, 18),
+            ]
+        );
+        assert_eq!(spans("\r\r\n\n1"), [(4, 4, 1, 5)]);
+        let span = Iterator::next(&mut JsonTextReader::new(" true")).unwrap().unwrap().span();
+        assert_eq!((span.len(), span.is_empty()), (4, false));
+    }
+
+    #[test]
+    fn errors_have_positions() {
+        let err = error("{\"a\": 1,\n  \"b\"\n  2}");
+        assert_eq!(err.kind(), SyntaxError::InvalidMemberValueDelimiter);
+        assert_eq!(
+            (err.position().offset(), err.position().line(), err.position().column()),
+            (17, 3, 3)
+        );
+        assert_eq!(err.to_string(), "InvalidMemberValueDelimiter at line 3, column 3");
+        let err = error("[1");
+        assert_eq!((err.kind(), err.position().column()), (SyntaxError::UnterminatedArray, 3));
+    }
+
+    #[test]
+    fn reader_position_follows_tokens() {
+        let mut reader = JsonTextReader::new("[ 1,\n2]");
+        assert_eq!(JsonTextReader::position(&reader), Position::default());
+        Iterator::next(&mut reader).unwrap().unwrap();
+        Iterator::next(&mut reader).unwrap().unwrap();
+        assert_eq!(JsonTextReader::position(&reader).column(), 4);
+        Iterator::next(&mut reader).unwrap().unwrap();
+        assert_eq!(JsonTextReader::position(&reader).line(), 2);
+    }
+
+    fn read_all(text: &str, options: ReaderOptions) -> Result<Vec<JsonToken<'_>>, ParseError> {
+        JsonTextReader::with_options(text, options).collect()
+    }
+
+    fn strict_error(text: &str) -> (SyntaxError, usize, usize) {
+        let err = read_all(text, ReaderOptions::strict()).unwrap_err();
+        (err.kind(), err.position().line(), err.position().column())
+    }
+
+    #[test]
+    fn strict_rejects_each_extension_unless_enabled() {
+        type Toggle = fn(ReaderOptions) -> ReaderOptions;
+        let cases: [(&str, SyntaxError, Toggle); 10] = [
+            ("[1 /* c */]", SyntaxError::UnexpectedComment, |o| o.comments(true)),
+            ("[1 // c\n]", SyntaxError::UnexpectedComment, |o| o.comments(true)),
+            ("[1 # c\n]", SyntaxError::UnexpectedComment, |o| o.hash_comments(true)),
+            ("['a']", SyntaxError::SingleQuotedString, |o| o.single_quoted_strings(true)),
+            ("[a]", SyntaxError::UnquotedString, |o| o.unquoted_strings(true)),
+            ("[1;2]", SyntaxError::UnterminatedArray, |o| o.semicolon_separators(true)),
+            (r#"{"a"=>1}"#, SyntaxError::InvalidMemberValueDelimiter, |o| {
+                o.assignment_delimiters(true)
+            }),
+            ("[1,]", SyntaxError::TrailingComma, |o| o.trailing_commas(true)),
+            ("[.5]", SyntaxError::InvalidNumber, |o| o.lenient_numbers(true)),
+            (r#"["\q"]"#, SyntaxError::InvalidEscape, |o| o.lenient_strings(true)),
+        ];
+        for (text, kind, enable) in cases {
+            assert_eq!(strict_error(text).0, kind, "{text}");
+            assert!(read_all(text, enable(ReaderOptions::strict())).is_ok(), "{text}");
+            assert!(read_all(text, ReaderOptions::default()).is_ok(), "{text}");
+        }
+    }
+
+    #[test]
+    fn strict_accepts_rfc_8259_text() {
+        let text = r#" {"a": [1, -0.5e+3, true, false, null, "\u00e9\n\/"], "b": {}} "#;
+        let tokens = read_all(text, ReaderOptions::strict()).unwrap();
+        assert_eq!(tokens.len(), 14);
+        assert_eq!(strict_error("[\"a\tb\"]").0, SyntaxError::ControlCharacter);
+        assert_eq!(strict_error("[01]").0, SyntaxError::InvalidNumber);
+        assert_eq!(strict_error("").0, SyntaxError::MissingValue);
+        assert_eq!(strict_error("[1] 2"), (SyntaxError::TrailingContent, 1, 5));
+    }
+
+    fn token(text: &str) -> JsonToken<'_> {
+        Iterator::next(&mut JsonTextReader::new(text)).unwrap().unwrap()
+    }
+
+    #[test]
+    fn as_str_decodes_strings() {
+        assert!(matches!(token(r#""abc""#).as_str(), Ok(Cow::Borrowed("abc"))));
+        assert!(matches!(token("'a\"b'").as_str(), Ok(Cow::Borrowed("a\"b"))));
+        assert!(matches!(token("abc").as_str(), Ok(Cow::Borrowed("abc"))));
+        let text = r#""\"\\\/\b\f\n\r\t\u00e9\ud83d\ude00\q""#;
+        assert_eq!(token(text).as_str().unwrap(), "\"\\/\x08\x0C\n\r\t\u{e9}\u{1F600}q");
+        let mut reader = JsonTextReader::new(r#"{"a\u0062": 1}"#);
+        Iterator::next(&mut reader).unwrap().unwrap();
+        let member = Iterator::next(&mut reader).unwrap().unwrap();
+        assert_eq!(member.kind(), JsonTokenKind::ObjectMember);
+        assert_eq!(member.as_str().unwrap(), "ab");
+    }
+
+    #[test]
+    fn as_str_reports_invalid_escapes() {
+        let err = token(r#""ab\u12""#).as_str().unwrap_err();
+        assert_eq!((err.kind(), err.position().column()), (SyntaxError::InvalidEscape, 4));
+        let err = token(r#""\ud800x""#).as_str().unwrap_err();
+        assert_eq!(err.kind(), SyntaxError::LoneSurrogate);
+    }
+
+    fn read_bytes(bytes: &[u8], options: ReaderOptions) -> Vec<Result<String, SyntaxError>> {
+        JsonTextReader::from_bytes_with_options(bytes, options)
+            .map(|token| token.map(|token| token.text().to_owned()).map_err(|err| err.kind()))
+            .collect()
+    }
+
+    #[test]
+    fn from_bytes_validates_utf8_as_it_reads() {
+        let tokens = read_bytes("[\"\u{e9}\", 1]".as_bytes(), ReaderOptions::default());
+        assert_eq!(
+            tokens,
+            [Ok("[".into()), Ok("\"\u{e9}\"".into()), Ok("1".into()), Ok("]".into())]
+        );
+        let tokens = read_bytes(b"[1, \"a\xFFb\"]", ReaderOptions::default());
+        assert_eq!(tokens, [Ok("[".into()), Ok("1".into()), Err(SyntaxError::InvalidUtf8)]);
+        let source = b"[\"abc\"]";
+        let mut reader = JsonTextReader::from_bytes(source);
+        Iterator::next(&mut reader).unwrap().unwrap();
+        let token = Iterator::next(&mut reader).unwrap().unwrap();
+        assert_eq!(token.as_borrowed_text(), Some("\"abc\""));
+    }
+
+    #[test]
+    fn from_bytes_replaces_invalid_utf8_in_strings_when_lossy() {
+        let options = ReaderOptions::default().lossy_utf8(true);
+        let tokens = read_bytes(b"[\"a\xFFb\xE2\x82\", 1]", options);
+        assert_eq!(tokens[1], Ok("\"a\u{FFFD}b\u{FFFD}\"".into()));
+        assert_eq!(tokens.len(), 4);
+        let tokens = read_bytes(b"[1, \xFF]", options);
+        assert_eq!(tokens.last(), Some(&Err(SyntaxError::InvalidUtf8)));
+    }
+
+    #[test]
+    fn max_depth_limits_nesting() {
+        let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(read_all(&nested(128), ReaderOptions::default()).is_ok());
+        let err = read_all(&nested(129), ReaderOptions::default()).unwrap_err();
+        assert_eq!((err.kind(), err.position().column()), (SyntaxError::DepthLimitExceeded, 129));
+        let options = ReaderOptions::default().max_depth(2);
+        assert!(read_all(r#"{"a": [1]}"#, options).is_ok());
+        let err = read_all(r#"{"a": [{}]}"#, options).unwrap_err();
+        assert_eq!(err.kind(), SyntaxError::DepthLimitExceeded);
+        let options = ReaderOptions::default().max_depth(0);
+        assert!(read_all("1", options).is_ok());
+        assert!(read_all("[]", options).is_err());
+    }
+
+    #[test]
+    fn depth_counts_enclosing_containers() {
+        let mut reader = JsonTextReader::new(r#"[{"a": 1}]"#);
+        let mut depths = Vec::new();
+        while let Some(token) = Iterator::next(&mut reader) {
+            depths.push((token.unwrap().kind(), reader.depth()));
         }
+        assert_eq!(
+            depths,
+            [
+                (JsonTokenKind::ArrayStart, 0),
+                (JsonTokenKind::ObjectStart, 1),
+                (JsonTokenKind::ObjectMember, 2),
+                (JsonTokenKind::Number, 2),
+                (JsonTokenKind::ObjectEnd, 1),
+                (JsonTokenKind::ArrayEnd, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_reject_oversized_input() {
+        let options = ReaderOptions::default().max_input_length(5);
+        assert!(read_all("[1,2]", options).is_ok());
+        let err = read_all("[1,22]", options).unwrap_err();
+        assert_eq!((err.kind(), err.position().offset()), (SyntaxError::InputLimitExceeded, 5));
+        assert!(read_all("[1,2]   ", options).is_ok());
+        let err = read_all("[1,2]   ", ReaderOptions::strict().max_input_length(5)).unwrap_err();
+        assert_eq!(err.kind(), SyntaxError::InputLimitExceeded);
+
+        let options = ReaderOptions::default().max_string_length(5);
+        assert!(read_all(r#"{"abc": "\n"}"#, options).is_ok());
+        let err = read_all(r#"["ab", "abcd"]"#, options).unwrap_err();
+        assert_eq!((err.kind(), err.position().column()), (SyntaxError::StringLimitExceeded, 8));
+        let err = read_all(r#"{"abcd": 1}"#, options).unwrap_err();
+        assert_eq!(err.kind(), SyntaxError::StringLimitExceeded);
+
+        let options = ReaderOptions::default().max_number_length(3);
+        assert!(read_all("[-12, 1e2]", options).is_ok());
+        let err = read_all("[1, 1234]", options).unwrap_err();
+        assert_eq!((err.kind(), err.position().column()), (SyntaxError::NumberLimitExceeded, 5));
+        assert_eq!(
+            read_all("[12ab]", options).unwrap_err().kind(),
+            SyntaxError::NumberLimitExceeded
+        );
+
+        let options = ReaderOptions::default().max_members(2);
+        assert!(read_all(r#"[[1, 2], {"a": 1, "b": 2}]"#, options).is_ok());
+        let err = read_all("[1, 2, 3]", options).unwrap_err();
+        assert_eq!((err.kind(), err.position().column()), (SyntaxError::MemberLimitExceeded, 8));
+        let err = read_all(r#"{"a": 1, "b": 2, "c": 3}"#, options).unwrap_err();
+        assert_eq!(err.kind(), SyntaxError::MemberLimitExceeded);
+    }
+
+    #[test]
+    fn duplicate_keys_can_be_an_error() {
+        let options = ReaderOptions::default().duplicate_keys(DuplicateKeys::Error);
+        assert!(read_all(r#"[{"a": 1}, {"a": 2}, {"b": {"a": 3}}]"#, options).is_ok());
+        let err = read_all(r#"{"a": 1, "\u0061": 2}"#, options).unwrap_err();
+        assert_eq!((err.kind(), err.position().column()), (SyntaxError::DuplicateKey, 10));
+    }
+
+    #[test]
+    fn trivia_covers_the_source() {
+        let text = "# head\n{ /* a */ \"a\": 1 // b\n} ";
+        let options = ReaderOptions::default().trivia(true);
+        let tokens = read_all(text, options).unwrap();
+        assert_eq!(tokens.first().unwrap().span().start().offset(), 0);
+        assert_eq!(tokens.last().unwrap().span().end().offset(), text.len());
+        let trivia: Vec<_> = tokens
+            .iter()
+            .filter(|token| {
+                matches!(token.kind(), JsonTokenKind::Comment | JsonTokenKind::Whitespace)
+            })
+            .map(|token| (token.kind(), token.comment_style(), token.text()))
+            .collect();
+        assert_eq!(
+            trivia,
+            [
+                (JsonTokenKind::Comment, Some(CommentStyle::Hash), "# head"),
+                (JsonTokenKind::Whitespace, None, "\n"),
+                (JsonTokenKind::Whitespace, None, " "),
+                (JsonTokenKind::Comment, Some(CommentStyle::Block), "/* a */"),
+                (JsonTokenKind::Whitespace, None, " "),
+                (JsonTokenKind::Whitespace, None, " "),
+                (JsonTokenKind::Whitespace, None, " "),
+                (JsonTokenKind::Comment, Some(CommentStyle::Line), "// b"),
+                (JsonTokenKind::Whitespace, None, "\n"),
+                (JsonTokenKind::Whitespace, None, " "),
+            ]
+        );
+        let text = "{a = 1; b => [2 ,3]}";
+        let tokens = read_all(text, options).unwrap();
+        let gaps: Vec<_> = tokens
+            .windows(2)
+            .map(|pair| &text[pair[0].span().end().offset()..pair[1].span().start().offset()])
+            .filter(|gap| !gap.is_empty())
+            .collect();
+        assert_eq!(gaps, ["=", ";", "=>", ","]);
+    }
+
+    #[test]
+    fn comment_tokens_keep_text_and_style() {
+        let text = "{\n  // The port.\n  \"port\": 80, /* default */\n  # end\n}";
+        let options = ReaderOptions::default().comment_tokens(true);
+        let tokens = read_all(text, options).unwrap();
+        let comments: Vec<_> = tokens
+            .iter()
+            .filter_map(|token| Some((token.comment_style()?, token.comment_text()?)))
+            .collect();
+        assert_eq!(
+            comments,
+            [
+                (CommentStyle::Line, " The port."),
+                (CommentStyle::Block, " default "),
+                (CommentStyle::Hash, " end"),
+            ]
+        );
+        assert!(tokens.iter().all(|token| token.kind() != JsonTokenKind::Whitespace));
+        assert_eq!(read_all(text, ReaderOptions::default()).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn tracks_current_pointer() {
+        let mut reader = JsonTextReader::new(r#"{"servers": [{"port": 1}, 2], "a/b~": {}}"#);
+        let mut pointers = Vec::new();
+        while let Some(token) = Iterator::next(&mut reader) {
+            pointers.push((token.unwrap().kind(), reader.current_pointer()));
+        }
+        let expected = [
+            (JsonTokenKind::ObjectStart, ""),
+            (JsonTokenKind::ObjectMember, "/servers"),
+            (JsonTokenKind::ArrayStart, "/servers"),
+            (JsonTokenKind::ObjectStart, "/servers/0"),
+            (JsonTokenKind::ObjectMember, "/servers/0/port"),
+            (JsonTokenKind::Number, "/servers/0/port"),
+            (JsonTokenKind::ObjectEnd, "/servers/0"),
+            (JsonTokenKind::Number, "/servers/1"),
+            (JsonTokenKind::ArrayEnd, "/servers"),
+            (JsonTokenKind::ObjectMember, "/a~1b~0"),
+            (JsonTokenKind::ObjectStart, "/a~1b~0"),
+            (JsonTokenKind::ObjectEnd, "/a~1b~0"),
+            (JsonTokenKind::ObjectEnd, ""),
+        ];
+        assert_eq!(pointers, expected.map(|(kind, pointer)| (kind, pointer.to_owned())));
+    }
+
+    #[test]
+    fn strict_rejects_non_string_member_names() {
+        for text in ["{1:2}", "{null:2}", "{true:2}", "{false:2}", "{-1.5:2}"] {
+            assert_eq!(strict_error(text), (SyntaxError::InvalidMemberName, 1, 2), "{text}");
+        }
+        assert!(read_all(r#"{"1":2}"#, ReaderOptions::strict()).is_ok());
+    }
+
+    #[test]
+    fn lenient_accepts_unquoted_member_names() {
+        let tokens = read_all("{1:2, null: 3, foo: 4}", ReaderOptions::default()).unwrap();
+        let names: Vec<_> = tokens
+            .iter()
+            .filter(|token| token.kind() == JsonTokenKind::ObjectMember)
+            .map(|token| token.text())
+            .collect();
+        assert_eq!(names, ["1", "null", "foo"]);
+    }
+
+    #[test]
+    fn strict_allows_only_json_whitespace() {
+        assert!(read_all(" \t\r\n[ \t\r\n1 \t\r\n] \t\r\n", ReaderOptions::strict()).is_ok());
+        for text in ["\0[1]", "[1,\x0B2]", "[1\x0C]", "[1]\x1F"] {
+            assert_eq!(strict_error(text).0, SyntaxError::UnexpectedCharacter, "{text:?}");
+        }
+        assert_eq!(strict_error("[1,\x0B2]"), (SyntaxError::UnexpectedCharacter, 1, 4));
+        assert!(read_all("\x0B[1,\x0C2]\0", ReaderOptions::default()).is_ok());
+        let options = ReaderOptions::strict().lenient_whitespace(true);
+        assert!(read_all("\x0B[1,\x0C2]", options).is_ok());
+    }
+
+    #[test]
+    fn strict_reports_trailing_content_at_second_value() {
+        assert_eq!(strict_error("1 2"), (SyntaxError::TrailingContent, 1, 3));
+        assert_eq!(strict_error("[1] [2]"), (SyntaxError::TrailingContent, 1, 5));
+        assert_eq!(strict_error("[1 2]"), (SyntaxError::UnterminatedArray, 1, 4));
+    }
+
+    #[test]
+    fn lenient_reads_spaced_unquoted_text_as_one_string() {
+        let tokens = read_all("[foo bar ]", ReaderOptions::default()).unwrap();
+        assert_eq!(tokens[1].kind(), JsonTokenKind::String);
+        assert_eq!(tokens[1].text(), "foo bar");
+    }
+
+    #[test]
+    fn borrowed_text_outlives_token() {
+        let source = String::from(r#"["a\u0062", "c"]"#);
+        let texts: Vec<&str> = JsonTextReader::new(&source)
+            .map(|token| token.unwrap().as_borrowed_text().unwrap())
+            .collect();
+        assert_eq!(texts, ["[", r#""a\u0062""#, r#""c""#, "]"]);
+        let mut reader = JsonTextReader::new("1");
+        let token = Iterator::next(&mut reader).unwrap().unwrap().into_owned();
+        assert_eq!(token.as_borrowed_text(), None);
+        assert_eq!(token.text(), "1");
     }
 }
@@ -0,0 +1,427 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! A tree of JSON values that borrows its strings and numbers from the text
+//! that was read, as far as possible, rather than copying them. Strings keep
+//! their escape sequences, which are only decoded when a string is asked for
+//! its value. Use [`JsonValue::into_owned`] to get a tree that can outlive
+//! the text, as a [`super::JsonValue`].
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::{self, Display},
+    io,
+    ops::Index,
+};
+
+use super::{
+    number, unescape,
+    value::{Tree, ValueBuilder},
+    writer, DuplicateKeys, JsonNumber, JsonTextReader, JsonTextWriter, JsonToken, JsonTokenKind,
+    ParseError, ReaderOptions, SyntaxError, WriteError,
+};
+
+/// The text of a string as written, without its quotes. Escape sequences
+/// were checked when reading but are only decoded by [`JsonStr::as_str`].
+#[derive(Debug, Clone)]
+pub struct JsonStr<'s> {
+    raw: Cow<'s, str>,
+    escaped: bool,
+}
+
+impl<'s> JsonStr<'s> {
+    fn from_token(token: &JsonToken<'s>) -> Result<Self, ParseError> {
+        let (raw, escaped) = token.as_raw_str()?;
+        Ok(Self { raw, escaped })
+    }
+
+    /// The text as written, with any escape sequences.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// Whether the text has any escape sequences to decode.
+    pub fn is_escaped(&self) -> bool {
+        self.escaped
+    }
+
+    /// Returns the value of the string, borrowing it from the text that was
+    /// read if there are no escape sequences to decode.
+    pub fn as_str(&self) -> Cow<'s, str> {
+        match (&self.raw, self.escaped) {
+            (Cow::Borrowed(raw), false) => Cow::Borrowed(raw),
+            (raw, false) => Cow::Owned(raw.to_string()),
+            (raw, true) => Cow::Owned(unescape(raw).expect("escape sequences were checked")),
+        }
+    }
+}
+
+/// Strings are equal if their values are, however they were written.
+impl PartialEq for JsonStr<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.escaped, other.escaped) {
+            (false, false) => self.raw == other.raw,
+            _ => self.as_str() == other.as_str(),
+        }
+    }
+}
+
+impl Display for JsonStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.as_str())
+    }
+}
+
+/// A JSON value held in memory, as a tree that borrows from the text it was
+/// read from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum JsonValue<'s> {
+    #[default]
+    Null,
+    Bool(bool),
+    /// A number in the decimal form specified by RFC 8259, which is only
+    /// copied if it was written in another form.
+    Number(Cow<'s, str>),
+    String(JsonStr<'s>),
+    Array(Vec<JsonValue<'s>>),
+    Object(JsonObject<'s>),
+}
+
+impl<'s> JsonValue<'s> {
+    /// Parses JSON text using the default, lenient, [`ReaderOptions`],
+    /// holding `NaN` and the infinities as strings as
+    /// [`super::JsonValue::parse`] does.
+    pub fn parse(text: &'s str) -> Result<JsonValue<'s>, ParseError> {
+        Self::parse_with_options(text, ReaderOptions::default())
+    }
+
+    /// Parses JSON text to its end as with
+    /// [`super::JsonValue::parse_with_options`].
+    pub fn parse_with_options(
+        text: &'s str,
+        options: ReaderOptions,
+    ) -> Result<JsonValue<'s>, ParseError> {
+        ValueBuilder::parse(text, options)
+    }
+
+    /// Reads the next value from a reader as with
+    /// [`super::JsonValue::from_reader`].
+    pub fn from_reader(reader: &mut JsonTextReader<'s>) -> Result<JsonValue<'s>, ParseError> {
+        ValueBuilder::read(reader)
+    }
+
+    /// Copies whatever is borrowed to make a tree that can outlive the text
+    /// it was read from.
+    pub fn into_owned(self) -> super::JsonValue {
+        match self {
+            JsonValue::Null => super::JsonValue::Null,
+            JsonValue::Bool(value) => super::JsonValue::Bool(value),
+            JsonValue::Number(text) => super::JsonValue::Number(JsonNumber(text.into_owned())),
+            JsonValue::String(value) => super::JsonValue::String(value.as_str().into_owned()),
+            JsonValue::Array(items) => {
+                super::JsonValue::Array(items.into_iter().map(JsonValue::into_owned).collect())
+            }
+            JsonValue::Object(object) => super::JsonValue::Object(object.into_owned()),
+        }
+    }
+
+    /// Writes the value to a writer, laid out as set by its options.
+    pub fn write_to<W: io::Write>(&self, writer: &mut JsonTextWriter<W>) -> Result<(), WriteError> {
+        match self {
+            JsonValue::Null => writer.write_null(),
+            JsonValue::Bool(value) => writer.write_bool(*value),
+            JsonValue::Number(text) => writer.write_raw_number(text),
+            JsonValue::String(value) => writer.write_string(&value.as_str()),
+            JsonValue::Array(items) => {
+                writer.write_start_array()?;
+                for item in items {
+                    item.write_to(writer)?;
+                }
+                writer.write_end_array()
+            }
+            JsonValue::Object(object) => {
+                writer.write_start_object()?;
+                for (name, value) in object.iter() {
+                    writer.write_member(&name.as_str())?;
+                    value.write_to(writer)?;
+                }
+                writer.write_end_object()
+            }
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the text of a number, in decimal form.
+    pub fn as_number(&self) -> Option<&str> {
+        match self {
+            JsonValue::Number(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// See [`super::JsonValue::as_i64`].
+    pub fn as_i64(&self) -> Option<i64> {
+        let n = number::to_i128(self.as_number()?).ok()?;
+        i64::try_from(n).ok()
+    }
+
+    /// See [`super::JsonValue::as_u64`].
+    pub fn as_u64(&self) -> Option<u64> {
+        let n = number::to_i128(self.as_number()?).ok()?;
+        u64::try_from(n).ok()
+    }
+
+    /// See [`super::JsonValue::as_f64`].
+    pub fn as_f64(&self) -> Option<f64> {
+        number::to_f64(self.as_number()?).ok()
+    }
+
+    /// Returns the value of a string, decoding any escape sequences, as
+    /// with [`JsonStr::as_str`].
+    pub fn as_str(&self) -> Option<Cow<'s, str>> {
+        match self {
+            JsonValue::String(value) => Some(value.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue<'s>]> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&JsonObject<'s>> {
+        match self {
+            JsonValue::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a member if this is an object that has it.
+    pub fn get(&self, name: &str) -> Option<&JsonValue<'s>> {
+        self.as_object().and_then(|object| object.get(name))
+    }
+
+    /// Returns an element if this is an array that has it.
+    pub fn at(&self, index: usize) -> Option<&JsonValue<'s>> {
+        self.as_array().and_then(|items| items.get(index))
+    }
+}
+
+static NULL: JsonValue<'static> = JsonValue::Null;
+
+/// See [`super::JsonValue`].
+impl<'s> Tree<'s> for JsonValue<'s> {
+    type Name = JsonStr<'s>;
+    type Object = JsonObject<'s>;
+
+    fn scalar(token: &JsonToken<'s>) -> Result<Self, ParseError> {
+        Ok(match token.kind() {
+            JsonTokenKind::True => JsonValue::Bool(true),
+            JsonTokenKind::False => JsonValue::Bool(false),
+            JsonTokenKind::Number if number::is_non_finite(token.text()) => {
+                JsonValue::String(JsonStr::from_token(token)?)
+            }
+            JsonTokenKind::Number => match token.as_decimal_str() {
+                Ok(text) => JsonValue::Number(text),
+                Err(_) => {
+                    let position = token.span().start();
+                    return Err(ParseError::new(SyntaxError::InvalidNumber, position));
+                }
+            },
+            JsonTokenKind::String => JsonValue::String(JsonStr::from_token(token)?),
+            _ => JsonValue::Null,
+        })
+    }
+
+    fn name(token: &JsonToken<'s>) -> Result<JsonStr<'s>, ParseError> {
+        JsonStr::from_token(token)
+    }
+
+    fn array(items: Vec<Self>) -> Self {
+        JsonValue::Array(items)
+    }
+
+    fn object(object: JsonObject<'s>) -> Self {
+        JsonValue::Object(object)
+    }
+
+    fn add(
+        object: &mut JsonObject<'s>,
+        name: JsonStr<'s>,
+        value: Self,
+        duplicate_keys: DuplicateKeys,
+    ) {
+        object.add(name, value, duplicate_keys);
+    }
+}
+
+impl<'s> Index<&str> for JsonValue<'s> {
+    type Output = JsonValue<'s>;
+
+    fn index(&self, name: &str) -> &JsonValue<'s> {
+        self.get(name).unwrap_or(&NULL)
+    }
+}
+
+/// See [`super::JsonValue`].
+impl<'s> Index<usize> for JsonValue<'s> {
+    type Output = JsonValue<'s>;
+
+    fn index(&self, index: usize) -> &JsonValue<'s> {
+        self.at(index).unwrap_or(&NULL)
+    }
+}
+
+/// See [`super::JsonValue`].
+impl Display for JsonValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writer::display(f, |writer| self.write_to(writer))
+    }
+}
+
+/// The members of a JSON object in the order they were read, with lookup by
+/// name in constant time, as with [`super::JsonObject`].
+#[derive(Debug, Clone, Default)]
+pub struct JsonObject<'s> {
+    members: Vec<(JsonStr<'s>, JsonValue<'s>)>,
+    /// The index in `members` of the last member by each name.
+    index: HashMap<Cow<'s, str>, usize>,
+}
+
+impl<'s> JsonObject<'s> {
+    /// Adds a member as read, following the policy for duplicate names.
+    fn add(&mut self, name: JsonStr<'s>, value: JsonValue<'s>, duplicate_keys: DuplicateKeys) {
+        let key = name.as_str();
+        match (self.index.get(&key), duplicate_keys) {
+            (Some(_), DuplicateKeys::FirstWins) => {}
+            (Some(&i), DuplicateKeys::Error | DuplicateKeys::LastWins) => {
+                self.members[i].1 = value;
+            }
+            _ => {
+                self.index.insert(key, self.members.len());
+                self.members.push((name, value));
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&JsonValue<'s>> {
+        self.index.get(name).map(|&i| &self.members[i].1)
+    }
+
+    /// Returns the values of all the members by a name, in order.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a JsonValue<'s>> {
+        self.members.iter().filter(move |(key, _)| key.as_str() == name).map(|(_, value)| value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&JsonStr<'s>, &JsonValue<'s>)> {
+        self.members.iter().map(|(name, value)| (name, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &JsonStr<'s>> {
+        self.members.iter().map(|(name, _)| name)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &JsonValue<'s>> {
+        self.members.iter().map(|(_, value)| value)
+    }
+
+    /// See [`JsonValue::into_owned`].
+    pub fn into_owned(self) -> super::JsonObject {
+        let mut object = super::JsonObject::new();
+        for (name, value) in self.members {
+            object.push(name.as_str(), value.into_owned());
+        }
+        object
+    }
+}
+
+/// Objects are equal if they have the same members in the same order.
+impl PartialEq for JsonObject<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.members == other.members
+    }
+}
+
+impl<'s> IntoIterator for JsonObject<'s> {
+    type Item = (JsonStr<'s>, JsonValue<'s>);
+    type IntoIter = std::vec::IntoIter<(JsonStr<'s>, JsonValue<'s>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.members.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrows_unescaped_text() {
+        let value = JsonValue::parse(r#"{"a": ["b\n", 1e2]}"#).unwrap();
+        let items = value["a"].as_array().unwrap();
+        let JsonValue::String(text) = &items[0] else { panic!("{value}") };
+        assert!(text.is_escaped());
+        assert_eq!(text.raw(), r"b\n");
+        assert_eq!(text.as_str(), "b\n");
+        assert!(matches!(items[1], JsonValue::Number(Cow::Borrowed("1e2"))));
+    }
+
+    #[test]
+    fn matches_owned_tree() {
+        let text = r#"{"a": 1, "b": [true, null, "xA"], "a": {"c": -0.5}}"#;
+        for duplicate_keys in [DuplicateKeys::FirstWins, DuplicateKeys::LastWins] {
+            let options = ReaderOptions::default().duplicate_keys(duplicate_keys);
+            let owned = super::super::JsonValue::parse_with_options(text, options).unwrap();
+            let borrowed = JsonValue::parse_with_options(text, options).unwrap();
+            assert_eq!(borrowed.into_owned(), owned);
+        }
+    }
+
+    #[test]
+    fn holds_non_finite_numbers_as_strings() {
+        let options = ReaderOptions::default();
+        let value = JsonValue::parse_with_options("[NaN, -Infinity, 0x1F]", options).unwrap();
+        let items = value.as_array().unwrap();
+        assert!(matches!(&items[0], JsonValue::String(text) if text.raw() == "NaN"));
+        assert!(matches!(&items[1], JsonValue::String(text) if text.raw() == "-Infinity"));
+        assert!(matches!(&items[2], JsonValue::Number(Cow::Owned(text)) if text == "31"));
+        let owned = super::super::JsonValue::parse("[NaN, -Infinity, 0x1F]").unwrap();
+        assert_eq!(value.into_owned(), owned);
+    }
+
+    #[test]
+    fn parse_enforces_trailing_content() {
+        let err = JsonValue::parse_with_options("[1] x", ReaderOptions::strict()).unwrap_err();
+        assert_eq!(err.kind(), SyntaxError::TrailingContent);
+        assert!(JsonValue::parse("[1] x").is_ok());
+    }
+}
@@ -0,0 +1,679 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! A concrete syntax tree of JSON text that keeps all of the text, down to
+//! comments, whitespace and delimiters, so that it can be edited and then
+//! written back with whatever was not edited exactly as it was. The text is
+//! read in trivia mode, see [`ReaderOptions::trivia`], and any non-standard
+//! forms accepted by the options, like unquoted strings, are kept as they
+//! are.
+//!
+//! New values are written compactly as strict JSON. New elements and
+//! members are laid out like their neighbours, as far as whitespace goes.
+
+use std::{
+    fmt::{self, Display},
+    ops::Range,
+};
+
+use super::{
+    CommentStyle, JsonTextReader, JsonToken, JsonTokenKind, JsonValue, ParseError, ReaderOptions,
+    SyntaxError,
+};
+
+/// JSON text as a tree that can be edited and turned back into text using
+/// [`Display`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonDocument {
+    /// The text before the value.
+    before: String,
+    root: JsonNode,
+    /// The text after the value, up to the end of the source.
+    after: String,
+}
+
+impl JsonDocument {
+    /// Parses JSON text using the default, lenient, [`ReaderOptions`].
+    pub fn parse(text: &str) -> Result<JsonDocument, ParseError> {
+        Self::parse_with_options(text, ReaderOptions::default())
+    }
+
+    /// Parses JSON text using the given options, with trivia mode turned on
+    /// whether set or not.
+    pub fn parse_with_options(
+        text: &str,
+        options: ReaderOptions,
+    ) -> Result<JsonDocument, ParseError> {
+        let mut builder = Builder {
+            source: text,
+            reader: JsonTextReader::with_options(text, options.trivia(true)),
+            offset: 0,
+            comments: Vec::new(),
+        };
+        let token = builder.next_token()?;
+        let before = builder.gap(&token).to_owned();
+        let root = builder.node(token)?;
+        //
+        // Read on for any error in what follows, like trailing content when
+        // that is not accepted.
+        //
+        while Iterator::next(&mut builder.reader).transpose()?.is_some() {}
+        let after = text[builder.offset..].to_owned();
+        Ok(JsonDocument { before, root, after })
+    }
+
+    pub fn root(&self) -> &JsonNode {
+        &self.root
+    }
+
+    pub fn root_mut(&mut self) -> &mut JsonNode {
+        &mut self.root
+    }
+}
+
+/// Writes the text of the document.
+impl Display for JsonDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.before, self.root, self.after)
+    }
+}
+
+/// A value in a [`JsonDocument`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonNode {
+    Scalar(JsonScalar),
+    Array(JsonArray),
+    Object(JsonObject),
+}
+
+impl JsonNode {
+    pub fn as_scalar(&self) -> Option<&JsonScalar> {
+        match self {
+            JsonNode::Scalar(scalar) => Some(scalar),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&JsonArray> {
+        match self {
+            JsonNode::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut JsonArray> {
+        match self {
+            JsonNode::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&JsonObject> {
+        match self {
+            JsonNode::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut JsonObject> {
+        match self {
+            JsonNode::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a member if this is an object that has one by
+    /// the name.
+    pub fn get(&self, name: &str) -> Option<&JsonNode> {
+        self.as_object()?.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut JsonNode> {
+        self.as_object_mut()?.get_mut(name)
+    }
+
+    /// Returns an element if this is an array that has one at the index.
+    pub fn at(&self, index: usize) -> Option<&JsonNode> {
+        self.as_array()?.get(index)
+    }
+
+    pub fn at_mut(&mut self, index: usize) -> Option<&mut JsonNode> {
+        self.as_array_mut()?.get_mut(index)
+    }
+
+    /// Replaces the value, keeping the text around it.
+    pub fn set(&mut self, value: impl Into<JsonValue>) {
+        *self = JsonNode::from(value.into());
+    }
+
+    /// Reads the value from its text using the default, lenient,
+    /// [`ReaderOptions`] without a depth limit. This fails where the text
+    /// holds something those options do not accept, like a string with an
+    /// invalid escape sequence or a form only accepted by the options the
+    /// document was parsed with.
+    pub fn to_value(&self) -> Result<JsonValue, ParseError> {
+        let options = ReaderOptions::default().max_depth(usize::MAX);
+        JsonValue::parse_with_options(&self.to_string(), options)
+    }
+}
+
+/// Makes a node written compactly as strict JSON.
+impl From<JsonValue> for JsonNode {
+    fn from(value: JsonValue) -> Self {
+        let kind = match value {
+            JsonValue::Array(items) => {
+                let list = List::compact(items.into_iter().map(JsonNode::from));
+                return JsonNode::Array(JsonArray { list });
+            }
+            JsonValue::Object(object) => {
+                let list = List::compact(object.into_iter().map(|(name, value)| Member {
+                    raw_name: JsonValue::from(name.as_str()).to_string(),
+                    name,
+                    delimiter: String::from(":"),
+                    value: JsonNode::from(value),
+                }));
+                return JsonNode::Object(JsonObject { list });
+            }
+            JsonValue::Null => JsonTokenKind::Null,
+            JsonValue::Bool(true) => JsonTokenKind::True,
+            JsonValue::Bool(false) => JsonTokenKind::False,
+            JsonValue::Number(_) => JsonTokenKind::Number,
+            JsonValue::String(_) => JsonTokenKind::String,
+        };
+        JsonNode::Scalar(JsonScalar { kind, text: value.to_string() })
+    }
+}
+
+/// Writes the text of the value.
+impl Display for JsonNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonNode::Scalar(scalar) => f.write_str(&scalar.text),
+            JsonNode::Array(array) => array.fmt(f),
+            JsonNode::Object(object) => object.fmt(f),
+        }
+    }
+}
+
+/// A null, boolean, number or string, by the text of its token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonScalar {
+    kind: JsonTokenKind,
+    text: String,
+}
+
+impl JsonScalar {
+    pub fn kind(&self) -> JsonTokenKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonArray {
+    list: List<JsonNode>,
+}
+
+impl JsonArray {
+    pub fn len(&self) -> usize {
+        self.list.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.items.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&JsonNode> {
+        self.list.items.get(index).map(|item| &item.content)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut JsonNode> {
+        self.list.items.get_mut(index).map(|item| &mut item.content)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &JsonNode> {
+        self.list.items.iter().map(|item| &item.content)
+    }
+
+    /// Inserts an element at an index, shifting those after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than the length.
+    pub fn insert(&mut self, index: usize, value: impl Into<JsonValue>) {
+        self.list.insert(index, JsonNode::from(value.into()));
+    }
+
+    pub fn push(&mut self, value: impl Into<JsonValue>) {
+        self.insert(self.len(), value);
+    }
+
+    /// Removes an element along with the comments around it, returning it.
+    pub fn remove(&mut self, index: usize) -> Option<JsonNode> {
+        self.list.remove(index)
+    }
+}
+
+impl Display for JsonArray {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.list)
+    }
+}
+
+/// The members of an object. An object normally has at most one member by
+/// a name, but where it has more, lookup by name finds the last of them,
+/// like [`super::JsonObject`] does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonObject {
+    list: List<Member>,
+}
+
+impl JsonObject {
+    pub fn len(&self) -> usize {
+        self.list.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.items.is_empty()
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.list.items.iter().rposition(|item| item.content.name == name)
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.find(name).is_some()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&JsonNode> {
+        self.find(name).map(|i| &self.list.items[i].content.value)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut JsonNode> {
+        self.find(name).map(|i| &mut self.list.items[i].content.value)
+    }
+
+    /// Returns the members, by their names with escape sequences decoded.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &JsonNode)> {
+        self.list.items.iter().map(|item| (item.content.name.as_str(), &item.content.value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.list.items.iter().map(|item| item.content.name.as_str())
+    }
+
+    /// Sets the value of a member, returning its previous value. A new member
+    /// is added at the end whereas an existing one keeps its place.
+    pub fn insert(&mut self, name: &str, value: impl Into<JsonValue>) -> Option<JsonNode> {
+        let value = JsonNode::from(value.into());
+        if let Some(i) = self.find(name) {
+            return Some(std::mem::replace(&mut self.list.items[i].content.value, value));
+        }
+        //
+        // Use the same spacing around the colon as the last member.
+        //
+        let delimiter = match self.list.items.last() {
+            Some(item) => {
+                let delimiter = &item.content.delimiter;
+                let end = delimiter.trim_end_matches(is_space).len();
+                format!("{}:{}", leading_space(delimiter), &delimiter[end..])
+            }
+            None => String::from(":"),
+        };
+        let member = Member {
+            raw_name: JsonValue::from(name).to_string(),
+            name: name.to_owned(),
+            delimiter,
+            value,
+        };
+        self.list.insert(self.len(), member);
+        None
+    }
+
+    /// Removes a member along with the comments around it, returning its
+    /// value.
+    pub fn remove(&mut self, name: &str) -> Option<JsonNode> {
+        let i = self.find(name)?;
+        self.list.remove(i).map(|member| member.value)
+    }
+}
+
+impl Display for JsonObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{{}}}", self.list)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Member {
+    /// The name as written, with any quotes and escape sequences.
+    raw_name: String,
+    name: String,
+    /// The text between the name and the value, such as `: `.
+    delimiter: String,
+    value: JsonNode,
+}
+
+impl Display for Member {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.raw_name, self.delimiter, self.value)
+    }
+}
+
+/// The elements or members of an array or object with the text around them,
+/// but not the brackets or braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct List<T> {
+    items: Vec<Item<T>>,
+    /// The text after the last item, or all of it when there are none.
+    tail: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Item<T> {
+    /// The text before the item, after what the previous one took.
+    before: String,
+    content: T,
+    /// The separator after the item, with any trivia before it, or empty
+    /// if there is none, as for the last item without a trailing comma.
+    separator: String,
+    /// The trivia after the separator, or after the item if there is none,
+    /// up to the end of the line, so that a comment there stays with it.
+    after: String,
+    /// Whether `after` ends with a line comment, so that whatever follows
+    /// must start on a new line.
+    line_comment: bool,
+}
+
+impl<T> List<T> {
+    /// Makes a list of items separated by commas alone.
+    fn compact(contents: impl Iterator<Item = T>) -> Self {
+        let mut items: Vec<Item<T>> = contents
+            .map(|content| Item {
+                before: String::new(),
+                content,
+                separator: String::from(","),
+                after: String::new(),
+                line_comment: false,
+            })
+            .collect();
+        if let Some(last) = items.last_mut() {
+            last.separator.clear();
+        }
+        List { items, tail: String::new() }
+    }
+
+    fn insert(&mut self, index: usize, content: T) {
+        let len = self.items.len();
+        assert!(index <= len, "index {index} is out of bounds for length {len}");
+        let before = match self.items.get(index).or_else(|| self.items.last()) {
+            Some(item) => layout(&item.before).to_owned(),
+            None => String::new(),
+        };
+        let mut separator = String::from(",");
+        if index == len {
+            //
+            // Follow a previous last item with a separator unless there is
+            // one already, in which case the new item gets one too so
+            // trailing commas are kept.
+            //
+            match self.items.last_mut() {
+                Some(last) if last.separator.is_empty() => {
+                    last.separator.push(',');
+                    separator.clear();
+                }
+                Some(_) => {}
+                None => separator.clear(),
+            }
+        }
+        let item = Item { before, content, separator, after: String::new(), line_comment: false };
+        self.items.insert(index, item);
+        if index > 0 {
+            self.break_line(index - 1);
+        }
+    }
+
+    fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.items.len() {
+            return None;
+        }
+        let item = self.items.remove(index);
+        match self.items.get_mut(index) {
+            //
+            // The first item stays where the removed one started.
+            //
+            Some(next) if index == 0 => {
+                let space = leading_space(&item.before);
+                next.before = format!("{}{}", space, next.before.trim_start_matches(is_space));
+            }
+            Some(_) => {}
+            None if item.separator.is_empty() => {
+                if let Some(last) = self.items.last_mut() {
+                    last.separator.pop();
+                }
+            }
+            None => {}
+        }
+        if index > 0 {
+            self.break_line(index - 1);
+        }
+        Some(item.content)
+    }
+
+    /// Makes sure that what follows an item that ends with a line comment
+    /// starts on a new line.
+    fn break_line(&mut self, index: usize) {
+        if !self.items[index].line_comment {
+            return;
+        }
+        let next = match self.items.get_mut(index + 1) {
+            Some(next) => &mut next.before,
+            None => &mut self.tail,
+        };
+        if !next.starts_with(['\n', '\r']) {
+            next.insert(0, '\n');
+        }
+    }
+}
+
+impl<T: Display> Display for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in &self.items {
+            write!(f, "{}{}{}{}", item.before, item.content, item.separator, item.after)?;
+        }
+        f.write_str(&self.tail)
+    }
+}
+
+fn is_space(ch: char) -> bool {
+    ch <= ' ' || ch == '\u{FEFF}'
+}
+
+/// The whitespace that starts the last line of the text before an item, or
+/// the whitespace that it starts with if it is all on one line, for laying
+/// out a new item like it.
+fn layout(before: &str) -> &str {
+    let start = match before.rfind(['\n', '\r']) {
+        Some(i) if before[..i].ends_with('\r') && before[i..].starts_with('\n') => i - 1,
+        Some(i) => i,
+        None => 0,
+    };
+    leading_space(&before[start..])
+}
+
+fn leading_space(text: &str) -> &str {
+    &text[..text.len() - text.trim_start_matches(is_space).len()]
+}
+
+/// Builds the tree from the tokens read in trivia mode, taking the text
+/// between them, which is trivia and delimiters, from the source.
+struct Builder<'s> {
+    source: &'s str,
+    reader: JsonTextReader<'s>,
+    /// The end of the last token taken.
+    offset: usize,
+    /// The comments read since the last token that is not trivia, by where
+    /// they are in the source and whether they are line comments.
+    comments: Vec<(Range<usize>, bool)>,
+}
+
+impl<'s> Builder<'s> {
+    /// Reads the next token that is not trivia.
+    fn next_token(&mut self) -> Result<JsonToken<'s>, ParseError> {
+        self.comments.clear();
+        loop {
+            let Some(token) = Iterator::next(&mut self.reader) else {
+                let position = JsonTextReader::position(&self.reader);
+                return Err(ParseError::new(SyntaxError::MissingValue, position));
+            };
+            let token = token?;
+            match token.comment_style() {
+                Some(style) => {
+                    let span = token.span();
+                    let range = span.start().offset()..span.end().offset();
+                    self.comments.push((range, style != CommentStyle::Block));
+                }
+                None if token.kind() == JsonTokenKind::Whitespace => {}
+                None => return Ok(token),
+            }
+        }
+    }
+
+    /// Takes the text between the last token taken and the next one.
+    fn gap(&mut self, token: &JsonToken<'_>) -> &'s str {
+        let span = token.span();
+        let text = &self.source[self.offset..span.start().offset()];
+        self.offset = span.end().offset();
+        text
+    }
+
+    fn node(&mut self, token: JsonToken<'s>) -> Result<JsonNode, ParseError> {
+        Ok(match token.kind() {
+            JsonTokenKind::ArrayStart => {
+                let list =
+                    self.list(JsonTokenKind::ArrayEnd, |builder, token| builder.node(token))?;
+                JsonNode::Array(JsonArray { list })
+            }
+            JsonTokenKind::ObjectStart => {
+                let list = self.list(JsonTokenKind::ObjectEnd, |builder, token| {
+                    let raw_name = token.text().to_owned();
+                    let name = token.as_str()?.into_owned();
+                    let token = builder.next_token()?;
+                    let delimiter = builder.gap(&token).to_owned();
+                    let value = builder.node(token)?;
+                    Ok(Member { raw_name, name, delimiter, value })
+                })?;
+                JsonNode::Object(JsonObject { list })
+            }
+            kind => JsonNode::Scalar(JsonScalar { kind, text: token.into_text().into_owned() }),
+        })
+    }
+
+    /// Reads the items of an array or object up to its end.
+    fn list<T>(
+        &mut self,
+        end: JsonTokenKind,
+        mut item: impl FnMut(&mut Self, JsonToken<'s>) -> Result<T, ParseError>,
+    ) -> Result<List<T>, ParseError> {
+        let mut items: Vec<Item<T>> = Vec::new();
+        loop {
+            let token = self.next_token()?;
+            let start = self.offset;
+            let mut before = self.gap(&token);
+            if let Some(last) = items.last_mut() {
+                before = self.split(start, before, last);
+            }
+            if token.kind() == end {
+                return Ok(List { items, tail: before.to_owned() });
+            }
+            let content = item(self, token)?;
+            items.push(Item {
+                before: before.to_owned(),
+                content,
+                separator: String::new(),
+                after: String::new(),
+                line_comment: false,
+            });
+        }
+    }
+
+    /// Splits the text that follows an item, which starts at `start` in the
+    /// source, into the separator, if any, and the trivia after it up to the
+    /// end of the line, both of which go with the item, and the rest, which
+    /// is returned.
+    fn split<T>(&self, start: usize, text: &'s str, item: &mut Item<T>) -> &'s str {
+        //
+        // Find the first character outside of comments that matches.
+        //
+        let find = |from: usize, f: fn(char) -> bool| {
+            let mut i = from;
+            while let Some(ch) = text[i..].chars().next() {
+                match self.comments.iter().find(|(range, _)| range.start == start + i) {
+                    Some((range, _)) => i = range.end - start,
+                    None if f(ch) => return Some(i),
+                    None => i += ch.len_utf8(),
+                }
+            }
+            None
+        };
+        let separator = find(0, |ch| !is_space(ch)).map_or(0, |i| i + 1);
+        item.separator = text[..separator].to_owned();
+        let Some(end) = find(separator, |ch| ch == '\n' || ch == '\r') else {
+            return &text[separator..];
+        };
+        item.after = text[separator..end].to_owned();
+        item.line_comment =
+            self.comments.iter().any(|(range, line)| *line && range.end == start + end);
+        &text[end..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_text() {
+        let text = "// head\n{ \"a\" : [1, /* one */ 2,], b: 'c' } // tail\n";
+        let document = JsonDocument::parse(text).unwrap();
+        assert_eq!(document.to_string(), text);
+    }
+
+    #[test]
+    fn edits_keep_layout() {
+        let text = "{\n  \"a\": 1, // one\n  \"b\": [1, 2]\n}";
+        let mut document = JsonDocument::parse(text).unwrap();
+        let root = document.root_mut().as_object_mut().unwrap();
+        root.insert("c", true);
+        root.get_mut("b").unwrap().as_array_mut().unwrap().push("x");
+        assert!(root.remove("a").is_some());
+        assert_eq!(document.to_string(), "{\n  \"b\": [1, 2, \"x\"],\n  \"c\": true\n}");
+    }
+
+    #[test]
+    fn node_from_value_matches_parsed_text() {
+        let value = JsonValue::parse(r#"{"a": [1, "x\ty", null, {}], "b": {"c": false}}"#).unwrap();
+        let text = value.to_string();
+        let parsed = JsonDocument::parse_with_options(&text, ReaderOptions::strict()).unwrap();
+        assert_eq!(JsonNode::from(value.clone()), parsed.root);
+        assert_eq!(JsonNode::from(value.clone()).to_value().unwrap(), value);
+    }
+
+    #[test]
+    fn node_from_deep_value_does_not_fail() {
+        let mut value = JsonValue::Null;
+        for _ in 0..200 {
+            value = JsonValue::Array(vec![value]);
+        }
+        let mut document = JsonDocument::parse("[]").unwrap();
+        document.root_mut().as_array_mut().unwrap().push(value.clone());
+        let root = document.root().to_value().unwrap();
+        assert_eq!(root, JsonValue::Array(vec![value]));
+    }
+}
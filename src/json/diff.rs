@@ -0,0 +1,377 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fmt::{self, Display};
+
+use super::{pointer, value::equivalent, JsonObject, JsonValue};
+
+/// How [`diff`] matches up the elements of arrays.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ArrayMatching {
+    /// Compare elements at the same index.
+    Index,
+    /// Match up elements that are equal wherever they are, so only elements
+    /// that were added or removed are reported, not moved ones.
+    Unordered,
+    /// Match up objects with the same value for the member by this name,
+    /// like `id`, wherever they are, and compare them. Other elements are
+    /// matched up as with [`ArrayMatching::Unordered`].
+    Key(String),
+}
+
+/// Controls how [`diff_with_options`] compares values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffOptions {
+    arrays: ArrayMatching,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self { arrays: ArrayMatching::Index }
+    }
+}
+
+impl DiffOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How to match up the elements of arrays. The default is
+    /// [`ArrayMatching::Index`].
+    pub fn arrays(mut self, value: ArrayMatching) -> Self {
+        self.arrays = value;
+        self
+    }
+}
+
+/// A difference between two values, at a JSON Pointer. The pointer of an
+/// element that was matched up with one at another index, or added, is its
+/// index in the new value, and that of a removed one is its index in the old.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added {
+        pointer: String,
+        value: JsonValue,
+    },
+    Removed {
+        pointer: String,
+        value: JsonValue,
+    },
+    /// A value changed to another of the same type.
+    Changed {
+        pointer: String,
+        old: JsonValue,
+        new: JsonValue,
+    },
+    /// A value changed to one of another type, like a number to a string.
+    TypeChanged {
+        pointer: String,
+        old: JsonValue,
+        new: JsonValue,
+    },
+}
+
+impl Change {
+    /// The name of the kind of change, as used by [`JsonDiff::to_value`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            Change::Added { .. } => "added",
+            Change::Removed { .. } => "removed",
+            Change::Changed { .. } => "changed",
+            Change::TypeChanged { .. } => "type_changed",
+        }
+    }
+
+    pub fn pointer(&self) -> &str {
+        match self {
+            Change::Added { pointer, .. }
+            | Change::Removed { pointer, .. }
+            | Change::Changed { pointer, .. }
+            | Change::TypeChanged { pointer, .. } => pointer,
+        }
+    }
+
+    /// The value before the change, unless it was added.
+    pub fn old_value(&self) -> Option<&JsonValue> {
+        match self {
+            Change::Added { .. } => None,
+            Change::Removed { value, .. } => Some(value),
+            Change::Changed { old, .. } | Change::TypeChanged { old, .. } => Some(old),
+        }
+    }
+
+    /// The value after the change, unless it was removed.
+    pub fn new_value(&self) -> Option<&JsonValue> {
+        match self {
+            Change::Added { value, .. } => Some(value),
+            Change::Removed { .. } => None,
+            Change::Changed { new, .. } | Change::TypeChanged { new, .. } => Some(new),
+        }
+    }
+
+    /// Returns the change as an object with the members `change`, `pointer`,
+    /// and `old` or `new` or both.
+    pub fn to_value(&self) -> JsonValue {
+        let mut object = JsonObject::new();
+        object.insert("change", self.name().into());
+        object.insert("pointer", self.pointer().into());
+        if let Some(old) = self.old_value() {
+            object.insert("old", old.clone());
+        }
+        if let Some(new) = self.new_value() {
+            object.insert("new", new.clone());
+        }
+        JsonValue::Object(object)
+    }
+}
+
+/// Writes the change, like `~ "/port": 80 -> 8080`, on one line,
+/// starting with `+` for an addition, `-` for a removal and `~` for a
+/// change. A change of type names the types.
+impl Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = JsonValue::from(self.pointer());
+        match self {
+            Change::Added { value, .. } => write!(f, "+ {pointer}: {value}"),
+            Change::Removed { value, .. } => write!(f, "- {pointer}: {value}"),
+            Change::Changed { old, new, .. } => write!(f, "~ {pointer}: {old} -> {new}"),
+            Change::TypeChanged { old, new, .. } => {
+                write!(f, "~ {pointer}: {} {old} -> {} {new}", type_name(old), type_name(new))
+            }
+        }
+    }
+}
+
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+/// The changes from one value to another, in the order of the old value,
+/// with additions to an object or array after the rest of its changes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonDiff {
+    changes: Vec<Change>,
+}
+
+impl JsonDiff {
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the changes as an array of objects, as with
+    /// [`Change::to_value`].
+    pub fn to_value(&self) -> JsonValue {
+        JsonValue::Array(self.changes.iter().map(Change::to_value).collect())
+    }
+}
+
+/// Writes the changes a line each, as with [`Change`].
+impl Display for JsonDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.changes.iter().try_for_each(|change| writeln!(f, "{change}"))
+    }
+}
+
+impl IntoIterator for JsonDiff {
+    type Item = Change;
+    type IntoIter = std::vec::IntoIter<Change>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.into_iter()
+    }
+}
+
+/// Compares two values using the default [`DiffOptions`]. Numbers are
+/// compared by value, however written, and members of objects by name,
+/// regardless of their order.
+pub fn diff(old: &JsonValue, new: &JsonValue) -> JsonDiff {
+    diff_with_options(old, new, DiffOptions::default())
+}
+
+pub fn diff_with_options(old: &JsonValue, new: &JsonValue, options: DiffOptions) -> JsonDiff {
+    let mut differ = Differ { options, pointer: String::new(), changes: Vec::new() };
+    differ.diff(old, new);
+    JsonDiff { changes: differ.changes }
+}
+
+struct Differ {
+    options: DiffOptions,
+    /// The pointer to the values being compared.
+    pointer: String,
+    changes: Vec<Change>,
+}
+
+impl Differ {
+    fn diff(&mut self, old: &JsonValue, new: &JsonValue) {
+        if equivalent(old, new) {
+            return;
+        }
+        match (old, new) {
+            (JsonValue::Object(old), JsonValue::Object(new)) => {
+                for (name, value) in old.iter() {
+                    match new.get(name) {
+                        Some(other) => self.at(name, |differ| differ.diff(value, other)),
+                        None => self.at(name, |differ| differ.removed(value)),
+                    }
+                }
+                for (name, value) in new.iter().filter(|(name, _)| !old.contains_key(name)) {
+                    self.at(name, |differ| differ.added(value));
+                }
+            }
+            (JsonValue::Array(old), JsonValue::Array(new)) => self.diff_arrays(old, new),
+            _ if type_name(old) == type_name(new) => {
+                let (old, new) = (old.clone(), new.clone());
+                self.changes.push(Change::Changed { pointer: self.pointer.clone(), old, new });
+            }
+            _ => {
+                let (old, new) = (old.clone(), new.clone());
+                self.changes.push(Change::TypeChanged { pointer: self.pointer.clone(), old, new });
+            }
+        }
+    }
+
+    fn diff_arrays(&mut self, old: &[JsonValue], new: &[JsonValue]) {
+        let key = match &self.options.arrays {
+            ArrayMatching::Index => {
+                for (index, value) in old.iter().enumerate() {
+                    match new.get(index) {
+                        Some(other) => {
+                            self.at(&index.to_string(), |differ| differ.diff(value, other))
+                        }
+                        None => self.at(&index.to_string(), |differ| differ.removed(value)),
+                    }
+                }
+                for (index, value) in new.iter().enumerate().skip(old.len()) {
+                    self.at(&index.to_string(), |differ| differ.added(value));
+                }
+                return;
+            }
+            ArrayMatching::Unordered => None,
+            ArrayMatching::Key(name) => Some(name.clone()),
+        };
+        fn key_of<'v>(value: &'v JsonValue, key: Option<&str>) -> Option<&'v JsonValue> {
+            value.as_object()?.get(key?)
+        }
+        let old_keys: Vec<_> = old.iter().map(|value| key_of(value, key.as_deref())).collect();
+        let new_keys: Vec<_> = new.iter().map(|value| key_of(value, key.as_deref())).collect();
+        //
+        // Match up each element of the new array with the first element of
+        // the old one that has the same key, or is equal if neither has one,
+        // and that was not matched up yet.
+        //
+        let mut matches = vec![None; new.len()];
+        let mut matched = vec![false; old.len()];
+        for (i, value) in new.iter().enumerate() {
+            let found = old.iter().enumerate().position(|(j, other)| {
+                !matched[j]
+                    && match (new_keys[i], old_keys[j]) {
+                        (Some(a), Some(b)) => equivalent(a, b),
+                        (None, None) => equivalent(value, other),
+                        _ => false,
+                    }
+            });
+            if let Some(j) = found {
+                matched[j] = true;
+                matches[i] = Some(j);
+            }
+        }
+        for (j, value) in old.iter().enumerate().filter(|&(j, _)| !matched[j]) {
+            self.at(&j.to_string(), |differ| differ.removed(value));
+        }
+        for (i, value) in new.iter().enumerate() {
+            match matches[i] {
+                Some(j) => self.at(&i.to_string(), |differ| differ.diff(&old[j], value)),
+                None => self.at(&i.to_string(), |differ| differ.added(value)),
+            }
+        }
+    }
+
+    /// Compares values at a member name or array index under the current
+    /// pointer.
+    fn at(&mut self, token: &str, f: impl FnOnce(&mut Self)) {
+        let len = self.pointer.len();
+        pointer::push(&mut self.pointer, token);
+        f(self);
+        self.pointer.truncate(len);
+    }
+
+    fn added(&mut self, value: &JsonValue) {
+        let pointer = self.pointer.clone();
+        self.changes.push(Change::Added { pointer, value: value.clone() });
+    }
+
+    fn removed(&mut self, value: &JsonValue) {
+        let pointer = self.pointer.clone();
+        self.changes.push(Change::Removed { pointer, value: value.clone() });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(text: &str) -> JsonValue {
+        JsonValue::parse(text).unwrap()
+    }
+
+    #[test]
+    fn reports_changes_by_pointer() {
+        let old = value(r#"{"a": 1, "b": "x", "c": [1, 2], "d": 1.0}"#);
+        let new = value(r#"{"a": 2, "b": 3, "c": [1], "d": 1, "e/f": null}"#);
+        let changes = diff(&old, &new);
+        assert_eq!(
+            changes.to_string(),
+            concat!(
+                "~ \"/a\": 1 -> 2\n",
+                "~ \"/b\": string \"x\" -> number 3\n",
+                "- \"/c/1\": 2\n",
+                "+ \"/e~1f\": null\n",
+            )
+        );
+        assert!(diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn matches_arrays_unordered() {
+        let options = DiffOptions::new().arrays(ArrayMatching::Unordered);
+        let changes = diff_with_options(&value("[1, 2, 3]"), &value("[3, 1, 4]"), options);
+        assert_eq!(changes.to_string(), "- \"/1\": 2\n+ \"/2\": 4\n");
+    }
+
+    #[test]
+    fn matches_arrays_by_key() {
+        let options = DiffOptions::new().arrays(ArrayMatching::Key(String::from("id")));
+        let old = value(r#"[{"id": 1, "v": "a"}, {"id": 2, "v": "b"}]"#);
+        let new = value(r#"[{"id": 2, "v": "c"}, {"id": 1, "v": "a"}]"#);
+        let changes = diff_with_options(&old, &new, options);
+        assert_eq!(changes.to_string(), "~ \"/0/v\": \"b\" -> \"c\"\n");
+        assert_eq!(
+            changes.to_value(),
+            value(r#"[{"change": "changed", "pointer": "/0/v", "old": "b", "new": "c"}]"#)
+        );
+    }
+
+    #[test]
+    fn writes_a_single_change_without_line_break() {
+        let change = Change::Added { pointer: String::from("/a"), value: JsonValue::Null };
+        assert_eq!(change.to_string(), "+ \"/a\": null");
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/// The encoding of JSON text given as bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+impl Encoding {
+    /// Detects the encoding of JSON text from its first bytes, using the
+    /// byte order mark if present, otherwise the pattern of null bytes
+    /// described in section 3 of RFC 4627:
+    ///
+    /// ```text
+    /// 00 00 00 xx  UTF-32BE
+    /// 00 xx 00 xx  UTF-16BE
+    /// xx 00 00 00  UTF-32LE
+    /// xx 00 xx 00  UTF-16LE
+    /// xx xx xx xx  UTF-8
+    /// ```
+    pub fn detect(bytes: &[u8]) -> Encoding {
+        Self::sniff(bytes, true).unwrap_or(Encoding::Utf8)
+    }
+
+    /// Detects the encoding from the bytes so far, returning `None` if more
+    /// are needed to tell, unless the bytes are `complete`.
+    pub(super) fn sniff(bytes: &[u8], complete: bool) -> Option<Encoding> {
+        match bytes {
+            [0, 0, 0xFE, 0xFF, ..] | [0, 0, ..] => Some(Encoding::Utf32Be),
+            [0xFE, 0xFF, ..] | [0, _, ..] => Some(Encoding::Utf16Be),
+            [0xFF, 0xFE, 0, 0, ..] | [_, 0, 0, 0, ..] => Some(Encoding::Utf32Le),
+            [0xFF, 0xFE, _, _, ..] | [_, 0, _, _, ..] => Some(Encoding::Utf16Le),
+            [0xFF, 0xFE, ..] | [_, 0, ..] if complete => Some(Encoding::Utf16Le),
+            [0xFF, 0xFE, ..] | [_, 0, ..] => None,
+            [0xEF, 0xBB, 0xBF, ..] | [_, _, ..] => Some(Encoding::Utf8),
+            _ if complete => Some(Encoding::Utf8),
+            _ => None,
+        }
+    }
+
+    /// Decodes as much of the bytes as possible, appending the text to
+    /// `out`. Returns the number of bytes decoded and whether decoding
+    /// stopped at an invalid sequence rather than at an incomplete one at
+    /// the end.
+    pub(super) fn decode(self, bytes: &[u8], out: &mut String) -> (usize, bool) {
+        match self {
+            Encoding::Utf8 => match std::str::from_utf8(bytes) {
+                Ok(text) => {
+                    out.push_str(text);
+                    (bytes.len(), false)
+                }
+                Err(err) => {
+                    let valid = err.valid_up_to();
+                    out.push_str(std::str::from_utf8(&bytes[..valid]).expect("valid UTF-8"));
+                    (valid, err.error_len().is_some())
+                }
+            },
+            Encoding::Utf16Le => decode_utf16(bytes, out, u16::from_le_bytes),
+            Encoding::Utf16Be => decode_utf16(bytes, out, u16::from_be_bytes),
+            Encoding::Utf32Le => decode_utf32(bytes, out, u32::from_le_bytes),
+            Encoding::Utf32Be => decode_utf32(bytes, out, u32::from_be_bytes),
+        }
+    }
+}
+
+fn decode_utf16(bytes: &[u8], out: &mut String, unit: fn([u8; 2]) -> u16) -> (usize, bool) {
+    let unit = |i: usize| unit([bytes[i], bytes[i + 1]]);
+    let mut i = 0;
+    while i + 2 <= bytes.len() {
+        let hi = unit(i);
+        let (cp, len) = match hi {
+            0xD800..=0xDBFF if i + 4 > bytes.len() => break,
+            0xD800..=0xDBFF => match unit(i + 2) {
+                lo @ 0xDC00..=0xDFFF => {
+                    (0x10000 + ((u32::from(hi) - 0xD800) << 10) + (u32::from(lo) - 0xDC00), 4)
+                }
+                _ => return (i, true),
+            },
+            0xDC00..=0xDFFF => return (i, true),
+            cp => (u32::from(cp), 2),
+        };
+        out.push(char::from_u32(cp).expect("code point is not a surrogate"));
+        i += len;
+    }
+    (i, false)
+}
+
+fn decode_utf32(bytes: &[u8], out: &mut String, unit: fn([u8; 4]) -> u32) -> (usize, bool) {
+    let mut i = 0;
+    while i + 4 <= bytes.len() {
+        let Some(ch) = char::from_u32(unit([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]])) else {
+            return (i, true);
+        };
+        out.push(ch);
+        i += 4;
+    }
+    (i, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{JsonPushParser, JsonTextReader, SyntaxError};
+
+    fn encode(text: &str, encoding: Encoding) -> Vec<u8> {
+        match encoding {
+            Encoding::Utf8 => text.as_bytes().to_vec(),
+            Encoding::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            Encoding::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+            Encoding::Utf32Le => text.chars().flat_map(|ch| u32::from(ch).to_le_bytes()).collect(),
+            Encoding::Utf32Be => text.chars().flat_map(|ch| u32::from(ch).to_be_bytes()).collect(),
+        }
+    }
+
+    const ENCODINGS: [Encoding; 5] = [
+        Encoding::Utf8,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+        Encoding::Utf32Le,
+        Encoding::Utf32Be,
+    ];
+
+    #[test]
+    fn detects_encoding_with_and_without_bom() {
+        for encoding in ENCODINGS {
+            assert_eq!(Encoding::detect(&encode("[1]", encoding)), encoding);
+            assert_eq!(Encoding::detect(&encode("\u{FEFF}\"a\"", encoding)), encoding);
+        }
+        assert_eq!(Encoding::detect(b"1"), Encoding::Utf8);
+        assert_eq!(Encoding::detect(b""), Encoding::Utf8);
+        assert_eq!(Encoding::detect(b"1\0"), Encoding::Utf16Le);
+        assert_eq!(Encoding::sniff(b"1\0", false), None);
+        assert_eq!(Encoding::sniff(b"1", false), None);
+    }
+
+    #[test]
+    fn decode_stops_at_incomplete_or_invalid_sequences() {
+        let mut out = String::new();
+        assert_eq!(
+            Encoding::Utf16Le.decode(&encode("a\u{1F600}", Encoding::Utf16Le)[..4], &mut out),
+            (2, false)
+        );
+        assert_eq!(Encoding::Utf16Le.decode(&[0x61, 0, 0x00, 0xDC], &mut out), (2, true));
+        assert_eq!(Encoding::Utf32Be.decode(&[0, 0x11, 0, 0], &mut out), (0, true));
+        assert_eq!(Encoding::Utf8.decode(b"a\xE2\x82", &mut out), (1, false));
+        assert_eq!(Encoding::Utf8.decode(b"a\xFF", &mut out), (1, true));
+        assert_eq!(out, "aaaa");
+    }
+
+    #[test]
+    fn reads_documents_in_any_encoding() {
+        let text = "\u{FEFF}{\"caf\u{e9}\": [\"\u{1F600}\"]}";
+        for encoding in ENCODINGS {
+            let bytes = encode(text, encoding);
+            let mut reader = JsonTextReader::from_bytes(&bytes);
+            let texts: Vec<_> = reader.by_ref().map(|token| token.unwrap().into_text()).collect();
+            assert_eq!(
+                texts,
+                ["{", "\"caf\u{e9}\"", "[", "\"\u{1F600}\"", "]", "}"],
+                "{encoding:?}"
+            );
+            assert_eq!(reader.encoding(), encoding);
+
+            let mut parser = JsonPushParser::new();
+            let mut count = 0;
+            for byte in &bytes {
+                count += parser.feed(&[*byte]).map(Result::unwrap).count();
+            }
+            count += parser.finish().map(Result::unwrap).count();
+            assert_eq!((count, parser.encoding()), (6, Some(encoding)));
+        }
+    }
+
+    #[test]
+    fn reports_invalid_sequences_once_reached() {
+        let mut bytes = encode("[1, \"a", Encoding::Utf16Le);
+        bytes.extend_from_slice(&[0x00, 0xDC]);
+        let kinds: Vec<_> = JsonTextReader::from_bytes(&bytes)
+            .map(|token| token.map(|token| token.kind()).map_err(|err| err.kind()))
+            .collect();
+        assert_eq!(kinds.len(), 3);
+        assert_eq!(kinds[2], Err(SyntaxError::InvalidEncoding));
+    }
+}
@@ -0,0 +1,307 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::borrow::Cow;
+
+use super::{is_number, NumberError};
+
+/// The parts of a number in decimal form, as in `-12.5e3`, that has been
+/// checked for syntax but not for range.
+struct Decimal<'a> {
+    negative: bool,
+    int: &'a str,
+    frac: &'a str,
+    exp: Option<&'a str>,
+}
+
+fn split_sign(text: &str) -> (bool, &str) {
+    match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    }
+}
+
+fn digits(s: &str) -> (&str, &str) {
+    let n = s.bytes().take_while(u8::is_ascii_digit).count();
+    s.split_at(n)
+}
+
+/// Splits an integer in the non-standard hexadecimal (`0x1F`) or octal
+/// (`017`) form, without its sign, into its digits and radix. An integer
+/// with a leading zero and an 8 or 9 among its digits, as in `08`, is taken
+/// for a mistyped octal and is invalid rather than read as decimal.
+fn split_radix(unsigned: &str) -> Result<Option<(&str, u32)>, NumberError> {
+    let bytes = unsigned.as_bytes();
+    match bytes {
+        [b'0', b'x' | b'X', rest @ ..]
+            if !rest.is_empty() && rest.iter().all(u8::is_ascii_hexdigit) =>
+        {
+            Ok(Some((&unsigned[2..], 16)))
+        }
+        [b'0', rest @ ..] if !rest.is_empty() && rest.iter().all(|b| matches!(b, b'0'..=b'7')) => {
+            Ok(Some((&unsigned[1..], 8)))
+        }
+        [b'0', rest @ ..] if !rest.is_empty() && rest.iter().all(u8::is_ascii_digit) => {
+            Err(NumberError::Invalid)
+        }
+        _ => Ok(None),
+    }
+}
+
+fn split_decimal(text: &str) -> Option<Decimal> {
+    let (negative, s) = split_sign(text);
+    let (int, s) = digits(s);
+    let (frac, s) = match s.strip_prefix('.') {
+        Some(s) => digits(s),
+        None => ("", s),
+    };
+    if int.is_empty() && frac.is_empty() {
+        return None;
+    }
+    let exp = match s.strip_prefix(['e', 'E']) {
+        Some(exp) => match digits(split_sign(exp).1) {
+            (digits, "") if !digits.is_empty() => Some(exp),
+            _ => return None,
+        },
+        None if s.is_empty() => None,
+        None => return None,
+    };
+    Some(Decimal { negative, int, frac, exp })
+}
+
+fn is_infinity(unsigned: &str) -> bool {
+    unsigned.eq_ignore_ascii_case("inf") || unsigned.eq_ignore_ascii_case("infinity")
+}
+
+/// Whether the text is `NaN` or an infinity, which are numbers in a form
+/// accepted when lenient but have no JSON form.
+pub(super) fn is_non_finite(text: &str) -> bool {
+    let unsigned = split_sign(text).1;
+    is_infinity(unsigned) || unsigned.eq_ignore_ascii_case("nan")
+}
+
+/// Whether the text is a number in a form accepted when lenient: anything
+/// that [`f64::from_str`](std::str::FromStr::from_str) accepts plus the
+/// hexadecimal form.
+pub(super) fn is_lenient_number(text: &str) -> bool {
+    text.parse::<f64>().is_ok() || matches!(split_radix(split_sign(text).1), Ok(Some(_)))
+}
+
+fn with_sign(negative: bool, magnitude: u128) -> Result<i128, NumberError> {
+    if negative {
+        match i128::try_from(magnitude) {
+            Ok(n) => Ok(-n),
+            Err(_) if magnitude == i128::MIN.unsigned_abs() => Ok(i128::MIN),
+            Err(_) => Err(NumberError::Overflow),
+        }
+    } else {
+        i128::try_from(magnitude).map_err(|_| NumberError::Overflow)
+    }
+}
+
+pub(super) fn to_i128(text: &str) -> Result<i128, NumberError> {
+    let (negative, unsigned) = split_sign(text);
+    if let Some((digits, radix)) = split_radix(unsigned)? {
+        let magnitude = u128::from_str_radix(digits, radix).map_err(|_| NumberError::Overflow)?;
+        return with_sign(negative, magnitude);
+    }
+    if is_infinity(unsigned) {
+        return Err(NumberError::Overflow);
+    }
+    if unsigned.eq_ignore_ascii_case("nan") {
+        return Err(NumberError::Lossy);
+    }
+    let Decimal { negative, int, frac, exp } =
+        split_decimal(text).ok_or(NumberError::NotANumber)?;
+
+    //
+    // Work with the significant digits and a power of ten so that integral
+    // values written with a fraction or exponent, like 1.0 or 1e3, are
+    // converted exactly.
+    //
+    let mut exp = match exp {
+        None => 0,
+        Some(exp) => {
+            exp.parse::<i64>().unwrap_or(if exp.starts_with('-') { i64::MIN } else { i64::MAX })
+        }
+    };
+    let digits = format!("{int}{frac}");
+    let digits = digits.trim_start_matches('0');
+    let significant = digits.trim_end_matches('0');
+    if significant.is_empty() {
+        return Ok(0);
+    }
+    exp = exp
+        .saturating_sub(frac.len() as i64)
+        .saturating_add((digits.len() - significant.len()) as i64);
+    if exp < 0 {
+        return Err(NumberError::Lossy);
+    }
+    let magnitude = u32::try_from(exp)
+        .ok()
+        .and_then(|exp| 10u128.checked_pow(exp))
+        .zip(significant.parse::<u128>().ok())
+        .and_then(|(scale, significant)| significant.checked_mul(scale))
+        .ok_or(NumberError::Overflow)?;
+    with_sign(negative, magnitude)
+}
+
+pub(super) fn to_f64(text: &str) -> Result<f64, NumberError> {
+    let (negative, unsigned) = split_sign(text);
+    if let Some((digits, radix)) = split_radix(unsigned)? {
+        let magnitude = u128::from_str_radix(digits, radix).map_err(|_| NumberError::Overflow)?;
+        let value = magnitude as f64;
+        return Ok(if negative { -value } else { value });
+    }
+    let value = text.parse::<f64>().map_err(|_| NumberError::NotANumber)?;
+    if value.is_infinite() && !is_infinity(unsigned) {
+        Err(NumberError::Overflow)
+    } else {
+        Ok(value)
+    }
+}
+
+pub(super) fn to_decimal_str(text: &str) -> Result<Cow<str>, NumberError> {
+    if is_number(text) {
+        return Ok(Cow::Borrowed(text));
+    }
+    let (negative, unsigned) = split_sign(text);
+    let sign = if negative { "-" } else { "" };
+    if let Some((digits, radix)) = split_radix(unsigned)? {
+        let magnitude = u128::from_str_radix(digits, radix).map_err(|_| NumberError::Overflow)?;
+        return Ok(Cow::Owned(format!("{sign}{magnitude}")));
+    }
+    if is_infinity(unsigned) {
+        return Err(NumberError::Overflow);
+    }
+    let Decimal { int, frac, exp, .. } = split_decimal(text).ok_or(NumberError::NotANumber)?;
+    let int = match int.trim_start_matches('0') {
+        "" => "0",
+        int => int,
+    };
+    let mut result = format!("{sign}{int}");
+    if !frac.is_empty() {
+        result.push('.');
+        result.push_str(frac);
+    }
+    if let Some(exp) = exp {
+        result.push('e');
+        result.push_str(exp);
+    }
+    Ok(Cow::Owned(result))
+}
+
+/// Formats a finite number as ECMAScript's `Number.prototype.toString` does,
+/// which RFC 8785 requires, using the fewest digits that round-trip.
+pub(super) fn to_es_string(value: f64) -> String {
+    if value == 0.0 {
+        return "0".to_owned();
+    }
+    if value < 0.0 {
+        return format!("-{}", to_es_string(-value));
+    }
+    //
+    // The exponential form that Rust writes has the fewest digits that
+    // round-trip, as in `1.2345e-7`, which is then laid out again.
+    //
+    let exp_form = format!("{value:e}");
+    let (mantissa, exp) = exp_form.split_once('e').expect("exponential form");
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exp.parse::<i32>().expect("exponent") + 1;
+    match n {
+        n if k <= n && n <= 21 => format!("{digits}{}", "0".repeat((n - k) as usize)),
+        n if 0 < n && n <= 21 => format!("{}.{}", &digits[..n as usize], &digits[n as usize..]),
+        n if -6 < n && n <= 0 => format!("0.{}{digits}", "0".repeat(-n as usize)),
+        n => {
+            let sign = if n > 0 { '+' } else { '-' };
+            match digits.split_at(1) {
+                (first, "") => format!("{first}e{sign}{}", (n - 1).abs()),
+                (first, rest) => format!("{first}.{rest}e{sign}{}", (n - 1).abs()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::JsonTextReader;
+
+    #[test]
+    fn converts_to_integers() {
+        assert_eq!(to_i128("42"), Ok(42));
+        assert_eq!(to_i128("-1.0"), Ok(-1));
+        assert_eq!(to_i128("1.5e1"), Ok(15));
+        assert_eq!(to_i128("1200e-2"), Ok(12));
+        assert_eq!(to_i128("0.0e99999999999"), Ok(0));
+        assert_eq!(to_i128("0x1F"), Ok(31));
+        assert_eq!(to_i128("-017"), Ok(-15));
+        assert_eq!(to_i128("08"), Err(NumberError::Invalid));
+        assert_eq!(to_i128("-019"), Err(NumberError::Invalid));
+        assert_eq!(to_i128("08.5"), Err(NumberError::Lossy));
+        assert_eq!(to_i128("-170141183460469231731687303715884105728"), Ok(i128::MIN));
+        assert_eq!(to_i128("170141183460469231731687303715884105728"), Err(NumberError::Overflow));
+        assert_eq!(to_i128("1e39"), Err(NumberError::Overflow));
+        assert_eq!(to_i128("1.5"), Err(NumberError::Lossy));
+        assert_eq!(to_i128("1e-1"), Err(NumberError::Lossy));
+        assert_eq!(to_i128("NaN"), Err(NumberError::Lossy));
+        assert_eq!(to_i128("-Infinity"), Err(NumberError::Overflow));
+    }
+
+    #[test]
+    fn converts_to_floating_point() {
+        assert_eq!(to_f64("-2.5e-3"), Ok(-0.0025));
+        assert_eq!(to_f64("0x10"), Ok(16.0));
+        assert_eq!(to_f64("08"), Err(NumberError::Invalid));
+        assert_eq!(to_f64("1e400"), Err(NumberError::Overflow));
+        assert_eq!(to_f64("-Infinity"), Ok(f64::NEG_INFINITY));
+        assert!(to_f64("NaN").unwrap().is_nan());
+        assert!(["NaN", "-nan", "+Infinity", "inf"].into_iter().all(is_non_finite));
+        assert!(!["1e400", "0x1F", "Nope"].into_iter().any(is_non_finite));
+    }
+
+    #[test]
+    fn converts_to_decimal_text() {
+        assert!(matches!(to_decimal_str("-1.5e+3"), Ok(Cow::Borrowed("-1.5e+3"))));
+        assert_eq!(to_decimal_str("+.5").unwrap(), "0.5");
+        assert_eq!(to_decimal_str("007.").unwrap(), "7");
+        assert_eq!(to_decimal_str("-0x1F").unwrap(), "-31");
+        assert_eq!(to_decimal_str("08"), Err(NumberError::Invalid));
+        assert_eq!(to_decimal_str("12345678901234567890123").unwrap(), "12345678901234567890123");
+        assert_eq!(to_decimal_str("Infinity"), Err(NumberError::Overflow));
+    }
+
+    #[test]
+    fn token_accessors_check_range_and_kind() {
+        let tokens: Vec<_> =
+            JsonTextReader::new("[-1, 1e19, 1.25, \"1\"]").map(Result::unwrap).collect();
+        assert_eq!(tokens[1].as_i64(), Ok(-1));
+        assert_eq!(tokens[1].as_u64(), Err(NumberError::Overflow));
+        assert_eq!(tokens[2].as_i64(), Err(NumberError::Overflow));
+        assert_eq!(tokens[2].as_u64(), Ok(10_000_000_000_000_000_000));
+        assert_eq!(tokens[3].as_f64(), Ok(1.25));
+        assert_eq!(tokens[3].as_i128(), Err(NumberError::Lossy));
+        assert_eq!(tokens[4].as_f64(), Err(NumberError::NotANumber));
+        assert_eq!(tokens[4].as_decimal_str(), Err(NumberError::NotANumber));
+    }
+
+    #[test]
+    fn formats_as_ecmascript() {
+        assert_eq!(to_es_string(-0.0), "0");
+        assert_eq!(to_es_string(1.0), "1");
+        assert_eq!(to_es_string(-1.5), "-1.5");
+        assert_eq!(to_es_string(123e18), "123000000000000000000");
+        assert_eq!(to_es_string(1e21), "1e+21");
+        assert_eq!(to_es_string(1.5e300), "1.5e+300");
+        assert_eq!(to_es_string(0.000001), "0.000001");
+        assert_eq!(to_es_string(1.2345e-7), "1.2345e-7");
+        assert_eq!(to_es_string(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(to_es_string(f64::MAX), "1.7976931348623157e+308");
+        assert_eq!(to_es_string(5e-324), "5e-324");
+    }
+}
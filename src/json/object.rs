@@ -0,0 +1,201 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::HashMap;
+
+use super::JsonValue;
+
+/// The members of a JSON object in the order they were added, with lookup
+/// by name in constant time.
+///
+/// An object normally has at most one member by a name, but more can be
+/// added with [`JsonObject::push`], as when reading with
+/// [`DuplicateKeys::KeepAll`](super::DuplicateKeys::KeepAll). Lookup by name
+/// then finds the last of them, like JavaScript does.
+#[derive(Debug, Clone, Default)]
+pub struct JsonObject {
+    members: Vec<(String, JsonValue)>,
+    /// The index in `members` of the last member by each name.
+    index: HashMap<String, usize>,
+}
+
+impl JsonObject {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&JsonValue> {
+        self.find(name).map(|i| &self.members[i].1)
+    }
+
+    /// Returns the member by a name along with the name as held by the
+    /// object.
+    pub(super) fn get_key_value(&self, name: &str) -> Option<(&str, &JsonValue)> {
+        self.find(name).map(|i| (self.members[i].0.as_str(), &self.members[i].1))
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut JsonValue> {
+        self.find(name).map(|i| &mut self.members[i].1)
+    }
+
+    /// Returns the values of all the members by a name, in order.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a JsonValue> {
+        self.members.iter().filter(move |(key, _)| key == name).map(|(_, value)| value)
+    }
+
+    /// Sets the value of a member, returning its previous value. A new member
+    /// is added at the end whereas an existing one keeps its place.
+    pub fn insert(&mut self, name: impl Into<String>, value: JsonValue) -> Option<JsonValue> {
+        let name = name.into();
+        match self.find(&name) {
+            Some(i) => Some(std::mem::replace(&mut self.members[i].1, value)),
+            None => {
+                self.push(name, value);
+                None
+            }
+        }
+    }
+
+    /// Adds a member at the end, even if there already is one by the name.
+    pub fn push(&mut self, name: impl Into<String>, value: JsonValue) {
+        let name = name.into();
+        self.index.insert(name.clone(), self.members.len());
+        self.members.push((name, value));
+    }
+
+    /// Removes a member, returning its value. The members that follow it
+    /// move up to keep their order.
+    pub fn remove(&mut self, name: &str) -> Option<JsonValue> {
+        let i = self.find(name)?;
+        let (name, value) = self.members.remove(i);
+        for j in self.index.values_mut() {
+            if *j > i {
+                *j -= 1;
+            }
+        }
+        match self.members[..i].iter().rposition(|(key, _)| *key == name) {
+            Some(j) => self.index.insert(name, j),
+            None => self.index.remove(&name),
+        };
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &JsonValue)> {
+        self.members.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut JsonValue)> {
+        self.members.iter_mut().map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &JsonValue> {
+        self.members.iter().map(|(_, value)| value)
+    }
+}
+
+/// Objects are equal if they have the same members in the same order.
+impl PartialEq for JsonObject {
+    fn eq(&self, other: &Self) -> bool {
+        self.members == other.members
+    }
+}
+
+impl<S: Into<String>> FromIterator<(S, JsonValue)> for JsonObject {
+    fn from_iter<T: IntoIterator<Item = (S, JsonValue)>>(iter: T) -> Self {
+        let mut object = JsonObject::new();
+        for (name, value) in iter {
+            object.insert(name, value);
+        }
+        object
+    }
+}
+
+impl IntoIterator for JsonObject {
+    type Item = (String, JsonValue);
+    type IntoIter = std::vec::IntoIter<(String, JsonValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.members.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{DuplicateKeys, ReaderOptions};
+
+    fn parse(text: &str, duplicate_keys: DuplicateKeys) -> JsonObject {
+        let options = ReaderOptions::default().duplicate_keys(duplicate_keys);
+        match JsonValue::parse_with_options(text, options).unwrap() {
+            JsonValue::Object(object) => object,
+            value => panic!("not an object: {value}"),
+        }
+    }
+
+    #[test]
+    fn keeps_members_in_order() {
+        let mut object: JsonObject =
+            [("b", JsonValue::from(1)), ("a", JsonValue::from(2))].into_iter().collect();
+        assert_eq!(object.insert("b", JsonValue::from(3)), Some(JsonValue::from(1)));
+        assert_eq!(object.insert("c", JsonValue::from(4)), None);
+        assert_eq!(object.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+        assert_eq!(object.remove("b"), Some(JsonValue::from(3)));
+        assert_eq!(object.remove("b"), None);
+        assert_eq!(object.get("c"), Some(&JsonValue::from(4)));
+        assert_eq!(object.keys().collect::<Vec<_>>(), ["a", "c"]);
+    }
+
+    #[test]
+    fn lookup_finds_the_last_member_by_a_name() {
+        let mut object = JsonObject::new();
+        object.push("a", JsonValue::from(1));
+        object.push("b", JsonValue::from(2));
+        object.push("a", JsonValue::from(3));
+        assert_eq!(object.len(), 3);
+        assert_eq!(object.get("a"), Some(&JsonValue::from(3)));
+        assert_eq!(
+            object.get_all("a").collect::<Vec<_>>(),
+            [&JsonValue::from(1), &JsonValue::from(3)]
+        );
+        assert_eq!(object.remove("a"), Some(JsonValue::from(3)));
+        assert_eq!(object.get("a"), Some(&JsonValue::from(1)));
+        assert_eq!(object.remove("a"), Some(JsonValue::from(1)));
+        assert!(!object.contains_key("a"));
+        assert_eq!(object.get("b"), Some(&JsonValue::from(2)));
+    }
+
+    #[test]
+    fn reads_duplicate_keys_by_policy() {
+        let text = r#"{"a": 1, "b": 2, "a": 3}"#;
+        let first = parse(text, DuplicateKeys::FirstWins);
+        assert_eq!(JsonValue::Object(first).to_string(), r#"{"a":1,"b":2}"#);
+        let last = parse(text, DuplicateKeys::LastWins);
+        assert_eq!(JsonValue::Object(last).to_string(), r#"{"a":3,"b":2}"#);
+        let all = parse(text, DuplicateKeys::KeepAll);
+        assert_eq!(all.get("a"), Some(&JsonValue::from(3)));
+        assert_eq!(JsonValue::Object(all).to_string(), r#"{"a":1,"b":2,"a":3}"#);
+    }
+}
@@ -0,0 +1,686 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! JSON Patch, as specified by RFC 6902, for applying a sequence of
+//! changes, each addressed by a JSON Pointer, to a [`JsonValue`], and for
+//! generating the changes from one value to another. Also JSON Merge Patch,
+//! as specified by RFC 7396, for simpler changes that are written like the
+//! value they change, see [`merge_patch`].
+
+use std::{borrow::Cow, error::Error, fmt::Display};
+
+use super::{pointer, value::equivalent, JsonObject, JsonValue};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PatchErrorKind {
+    /// The operation is not an object with an `op` that is known and the
+    /// members that it requires.
+    InvalidOperation,
+    /// A pointer is neither empty nor starts with `/`, or has a `~` that is
+    /// not followed by `0` or `1`.
+    InvalidPointer,
+    /// There is no value where a pointer points, or for `add`, no object or
+    /// array to add it to at the index given.
+    PathNotFound,
+    /// A value is moved into one of its own members or elements.
+    MoveIntoChild,
+    /// The value of a `test` differs.
+    TestFailed,
+}
+
+impl Display for PatchErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl Error for PatchErrorKind {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchError {
+    kind: PatchErrorKind,
+    index: usize,
+    pointer: String,
+}
+
+impl PatchError {
+    fn new(kind: PatchErrorKind, index: usize, pointer: &str) -> Self {
+        Self { kind, index, pointer: pointer.to_owned() }
+    }
+
+    pub fn kind(&self) -> PatchErrorKind {
+        self.kind
+    }
+
+    /// The index of the failing operation in the patch.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The pointer of the failing operation that caused the error, which is
+    /// `from` if that is where it lies, and otherwise `path`.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} in operation {} at {:?}", self.kind, self.index, self.pointer)
+    }
+}
+
+impl Error for PatchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+/// An operation of a patch, where `path` and `from` are JSON Pointers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// Adds a member, replacing any by the same name, or inserts an element
+    /// at an index, where `-` stands for the end of the array.
+    Add {
+        path: String,
+        value: JsonValue,
+    },
+    /// Removes a member or element. Removing the whole value leaves `null`.
+    Remove {
+        path: String,
+    },
+    Replace {
+        path: String,
+        value: JsonValue,
+    },
+    /// Removes a value and adds it elsewhere.
+    Move {
+        from: String,
+        path: String,
+    },
+    Copy {
+        from: String,
+        path: String,
+    },
+    /// Checks that a value is equal to the one given, where numbers are
+    /// compared by value and objects without regard to member order.
+    Test {
+        path: String,
+        value: JsonValue,
+    },
+}
+
+impl Operation {
+    /// The name of the operation, as given by `op`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Add { .. } => "add",
+            Operation::Remove { .. } => "remove",
+            Operation::Replace { .. } => "replace",
+            Operation::Move { .. } => "move",
+            Operation::Copy { .. } => "copy",
+            Operation::Test { .. } => "test",
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            Operation::Add { path, .. }
+            | Operation::Remove { path }
+            | Operation::Replace { path, .. }
+            | Operation::Move { path, .. }
+            | Operation::Copy { path, .. }
+            | Operation::Test { path, .. } => path,
+        }
+    }
+
+    pub fn from(&self) -> Option<&str> {
+        match self {
+            Operation::Move { from, .. } | Operation::Copy { from, .. } => Some(from),
+            _ => None,
+        }
+    }
+
+    pub fn value(&self) -> Option<&JsonValue> {
+        match self {
+            Operation::Add { value, .. }
+            | Operation::Replace { value, .. }
+            | Operation::Test { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    fn from_value(object: &JsonValue, index: usize) -> Result<Operation, PatchError> {
+        let path = object.get("path").and_then(JsonValue::as_str);
+        let error = |kind| PatchError::new(kind, index, path.unwrap_or_default());
+        let Some(path) = path else {
+            return Err(error(PatchErrorKind::InvalidOperation));
+        };
+        let path = path.to_owned();
+        let from = || match object.get("from").and_then(JsonValue::as_str) {
+            Some(from) => Ok(from.to_owned()),
+            None => Err(error(PatchErrorKind::InvalidOperation)),
+        };
+        let value = || match object.get("value") {
+            Some(value) => Ok(value.clone()),
+            None => Err(error(PatchErrorKind::InvalidOperation)),
+        };
+        let operation = match object.get("op").and_then(JsonValue::as_str) {
+            Some("add") => Operation::Add { path, value: value()? },
+            Some("remove") => Operation::Remove { path },
+            Some("replace") => Operation::Replace { path, value: value()? },
+            Some("move") => Operation::Move { from: from()?, path },
+            Some("copy") => Operation::Copy { from: from()?, path },
+            Some("test") => Operation::Test { path, value: value()? },
+            _ => return Err(error(PatchErrorKind::InvalidOperation)),
+        };
+        for pointer in [Some(operation.path()), operation.from()].into_iter().flatten() {
+            split(pointer, index)?;
+        }
+        Ok(operation)
+    }
+
+    /// Returns the operation as an object, as it is written in a patch.
+    pub fn to_value(&self) -> JsonValue {
+        let mut object = JsonObject::new();
+        object.insert("op", self.name().into());
+        if let Some(from) = self.from() {
+            object.insert("from", from.into());
+        }
+        object.insert("path", self.path().into());
+        if let Some(value) = self.value() {
+            object.insert("value", value.clone());
+        }
+        JsonValue::Object(object)
+    }
+
+    fn apply(&self, target: &mut JsonValue, index: usize) -> Result<(), PatchError> {
+        let path = self.path();
+        let tokens = split(path, index)?;
+        match self {
+            Operation::Add { value, .. } => add(target, &tokens, value.clone()),
+            Operation::Remove { .. } => remove(target, &tokens).map(drop),
+            Operation::Replace { value, .. } => match pointer::get_mut(target, &tokens) {
+                Some(old) => {
+                    *old = value.clone();
+                    Ok(())
+                }
+                None => Err(PatchErrorKind::PathNotFound),
+            },
+            Operation::Move { from, .. } => {
+                let source = split(from, index)?;
+                let error = |kind| PatchError::new(kind, index, from);
+                if tokens == source {
+                    pointer::get(target, &source)
+                        .ok_or_else(|| error(PatchErrorKind::PathNotFound))?;
+                    return Ok(());
+                }
+                if tokens.starts_with(&source) {
+                    return Err(error(PatchErrorKind::MoveIntoChild));
+                }
+                let value = remove(target, &source).map_err(error)?;
+                add(target, &tokens, value)
+            }
+            Operation::Copy { from, .. } => {
+                let Some(value) = pointer::get(target, &split(from, index)?) else {
+                    return Err(PatchError::new(PatchErrorKind::PathNotFound, index, from));
+                };
+                add(target, &tokens, value.clone())
+            }
+            Operation::Test { value, .. } => match pointer::get(target, &tokens) {
+                Some(actual) if equivalent(actual, value) => Ok(()),
+                Some(_) => Err(PatchErrorKind::TestFailed),
+                None => Err(PatchErrorKind::PathNotFound),
+            },
+        }
+        .map_err(|kind| PatchError::new(kind, index, path))
+    }
+}
+
+fn split(pointer: &str, index: usize) -> Result<Vec<Cow<'_, str>>, PatchError> {
+    pointer::split(pointer)
+        .ok_or_else(|| PatchError::new(PatchErrorKind::InvalidPointer, index, pointer))
+}
+
+fn add(
+    target: &mut JsonValue,
+    tokens: &[Cow<'_, str>],
+    value: JsonValue,
+) -> Result<(), PatchErrorKind> {
+    let Some((last, parent)) = tokens.split_last() else {
+        *target = value;
+        return Ok(());
+    };
+    match pointer::get_mut(target, parent) {
+        Some(JsonValue::Object(object)) => {
+            object.insert(last.as_ref(), value);
+        }
+        Some(JsonValue::Array(items)) => {
+            let index = match last.as_ref() {
+                "-" => items.len(),
+                last => pointer::index(last)
+                    .filter(|&index| index <= items.len())
+                    .ok_or(PatchErrorKind::PathNotFound)?,
+            };
+            items.insert(index, value);
+        }
+        _ => return Err(PatchErrorKind::PathNotFound),
+    }
+    Ok(())
+}
+
+fn remove(target: &mut JsonValue, tokens: &[Cow<'_, str>]) -> Result<JsonValue, PatchErrorKind> {
+    let Some((last, parent)) = tokens.split_last() else {
+        return Ok(std::mem::take(target));
+    };
+    let removed = match pointer::get_mut(target, parent) {
+        Some(JsonValue::Object(object)) => object.remove(last),
+        Some(JsonValue::Array(items)) => pointer::index(last)
+            .filter(|&index| index < items.len())
+            .map(|index| items.remove(index)),
+        _ => None,
+    };
+    removed.ok_or(PatchErrorKind::PathNotFound)
+}
+
+/// A sequence of operations to apply to a value, in order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonPatch {
+    operations: Vec<Operation>,
+}
+
+impl JsonPatch {
+    pub fn new(operations: Vec<Operation>) -> Self {
+        Self { operations }
+    }
+
+    /// Reads a patch from its document, which is an array of operations.
+    /// A value that is not an array is an invalid operation at index 0.
+    pub fn from_value(value: &JsonValue) -> Result<JsonPatch, PatchError> {
+        let Some(items) = value.as_array() else {
+            return Err(PatchError::new(PatchErrorKind::InvalidOperation, 0, ""));
+        };
+        let operations = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| Operation::from_value(item, index))
+            .collect::<Result<_, _>>()?;
+        Ok(JsonPatch { operations })
+    }
+
+    /// Returns the patch as it is written, as an array of operations.
+    pub fn to_value(&self) -> JsonValue {
+        JsonValue::Array(self.operations.iter().map(Operation::to_value).collect())
+    }
+
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Applies the operations to a copy of the target that replaces it only
+    /// if they all succeed, so that the target is left as it was if any
+    /// fails.
+    pub fn apply(&self, target: &mut JsonValue) -> Result<(), PatchError> {
+        let mut patched = target.clone();
+        for (index, operation) in self.operations.iter().enumerate() {
+            operation.apply(&mut patched, index)?;
+        }
+        *target = patched;
+        Ok(())
+    }
+
+    /// Generates a patch that turns one value into another, made of `add`,
+    /// `remove` and `replace` operations. Values are compared as by `test`,
+    /// so that the order of members is not considered, and elements of
+    /// arrays are matched up so that as few as possible are added or
+    /// removed.
+    pub fn diff(from: &JsonValue, to: &JsonValue) -> JsonPatch {
+        let mut operations = Vec::new();
+        diff(from, to, &mut String::new(), &mut operations);
+        JsonPatch { operations }
+    }
+}
+
+impl FromIterator<Operation> for JsonPatch {
+    fn from_iter<T: IntoIterator<Item = Operation>>(iter: T) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for JsonPatch {
+    type Item = Operation;
+    type IntoIter = std::vec::IntoIter<Operation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.operations.into_iter()
+    }
+}
+
+fn diff(from: &JsonValue, to: &JsonValue, path: &mut String, operations: &mut Vec<Operation>) {
+    if equivalent(from, to) {
+        return;
+    }
+    let len = path.len();
+    match (from, to) {
+        (JsonValue::Object(from), JsonValue::Object(to)) => {
+            for name in from.keys().filter(|name| !to.contains_key(name)) {
+                pointer::push(path, name);
+                operations.push(Operation::Remove { path: path.clone() });
+                path.truncate(len);
+            }
+            for (name, value) in to.iter() {
+                pointer::push(path, name);
+                match from.get(name) {
+                    Some(old) => diff(old, value, path, operations),
+                    None => {
+                        operations.push(Operation::Add { path: path.clone(), value: value.clone() })
+                    }
+                }
+                path.truncate(len);
+            }
+        }
+        (JsonValue::Array(from), JsonValue::Array(to)) => diff_arrays(from, to, path, operations),
+        _ => operations.push(Operation::Replace { path: path.clone(), value: to.clone() }),
+    }
+}
+
+/// The most cells of the table for matching up the elements of two arrays,
+/// beyond which elements are simply paired up in order.
+const MAX_TABLE_SIZE: usize = 1 << 20;
+
+fn diff_arrays(
+    from: &[JsonValue],
+    to: &[JsonValue],
+    path: &mut String,
+    operations: &mut Vec<Operation>,
+) {
+    //
+    // Leave out the elements that the arrays start and end with in common,
+    // then match up the rest by their longest common subsequence.
+    //
+    let start = from.iter().zip(to).take_while(|(a, b)| equivalent(a, b)).count();
+    let end = from[start..]
+        .iter()
+        .rev()
+        .zip(to[start..].iter().rev())
+        .take_while(|(a, b)| equivalent(a, b))
+        .count();
+    let (from, to) = (&from[start..from.len() - end], &to[start..to.len() - end]);
+    let (n, m) = (from.len(), to.len());
+    let table = (n + 1).checked_mul(m + 1).filter(|&size| size <= MAX_TABLE_SIZE).map(|size| {
+        //
+        // The length of the longest common subsequence of from[i..] and
+        // to[j..] at i * (m + 1) + j.
+        //
+        let mut table = vec![0; size];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                table[i * (m + 1) + j] = match equivalent(&from[i], &to[j]) {
+                    true => table[(i + 1) * (m + 1) + j + 1] + 1,
+                    false => table[(i + 1) * (m + 1) + j].max(table[i * (m + 1) + j + 1]),
+                };
+            }
+        }
+        table
+    });
+    let lcs = |i: usize, j: usize| table.as_ref().map_or(0, |table| table[i * (m + 1) + j]);
+    let len = path.len();
+    let (mut i, mut j, mut index) = (0, 0, start);
+    while i < n || j < m {
+        pointer::push(path, &index.to_string());
+        if i < n && j < m && equivalent(&from[i], &to[j]) {
+            (i, j, index) = (i + 1, j + 1, index + 1);
+        } else if i < n && j < m && lcs(i + 1, j + 1) == lcs(i, j) {
+            //
+            // Neither element is needed for the subsequence so one can be
+            // changed into the other.
+            //
+            diff(&from[i], &to[j], path, operations);
+            (i, j, index) = (i + 1, j + 1, index + 1);
+        } else if i < n && (j == m || lcs(i + 1, j) == lcs(i, j)) {
+            operations.push(Operation::Remove { path: path.clone() });
+            i += 1;
+        } else {
+            operations.push(Operation::Add { path: path.clone(), value: to[j].clone() });
+            (j, index) = (j + 1, index + 1);
+        }
+        path.truncate(len);
+    }
+}
+
+/// Applies a merge patch to a value. A patch that is an object changes the
+/// members of the target by name, where a member set to `null` is removed,
+/// a member set to an object is merged likewise and any other value replaces
+/// the member, if any. Members that remain keep their place and new ones
+/// are added at the end, in the order of the patch. A patch that is not an
+/// object replaces the target.
+pub fn merge_patch(target: &mut JsonValue, patch: &JsonValue) {
+    let JsonValue::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !matches!(target, JsonValue::Object(_)) {
+        *target = JsonValue::Object(JsonObject::new());
+    }
+    let JsonValue::Object(object) = target else {
+        unreachable!();
+    };
+    for (name, value) in patch.iter() {
+        if value.is_null() {
+            while object.remove(name).is_some() {}
+            continue;
+        }
+        match object.get_mut(name) {
+            Some(member) => merge_patch(member, value),
+            None => {
+                let mut member = JsonValue::Null;
+                merge_patch(&mut member, value);
+                object.insert(name, member);
+            }
+        }
+    }
+}
+
+/// Generates a merge patch that turns one value into another, as applied by
+/// [`merge_patch`]. Values are compared as by [`Operation::Test`]. A merge
+/// patch cannot set a member to `null`, so a member that is `null` in `to`
+/// is removed rather than changed when the patch is applied, and arrays are
+/// always replaced as a whole.
+pub fn diff_merge_patch(from: &JsonValue, to: &JsonValue) -> JsonValue {
+    let (JsonValue::Object(from), JsonValue::Object(to)) = (from, to) else {
+        return to.clone();
+    };
+    let mut patch = JsonObject::new();
+    for name in from.keys().filter(|name| !to.contains_key(name)) {
+        patch.insert(name, JsonValue::Null);
+    }
+    for (name, value) in to.iter() {
+        match from.get(name) {
+            Some(old) if equivalent(old, value) => {}
+            Some(old) => {
+                patch.insert(name, diff_merge_patch(old, value));
+            }
+            None => {
+                patch.insert(name, value.clone());
+            }
+        }
+    }
+    JsonValue::Object(patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> JsonValue {
+        JsonValue::parse(text).unwrap()
+    }
+
+    fn apply(target: &str, patch: &str) -> Result<String, PatchError> {
+        let mut target = parse(target);
+        JsonPatch::from_value(&parse(patch))?.apply(&mut target)?;
+        Ok(target.to_string())
+    }
+
+    #[test]
+    fn applies_operations() {
+        let cases = [
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "add", "path": "/baz", "value": "qux"}]"#,
+                r#"{"foo":"bar","baz":"qux"}"#,
+            ),
+            (
+                r#"["a", "c"]"#,
+                concat!(
+                    r#"[{"op": "add", "path": "/1", "value": "b"},"#,
+                    r#" {"op": "add", "path": "/-", "value": "d"}]"#
+                ),
+                r#"["a","b","c","d"]"#,
+            ),
+            (r#"{"a": 1, "b": 2}"#, r#"[{"op": "remove", "path": "/a"}]"#, r#"{"b":2}"#),
+            (
+                r#"{"a": [1, 2]}"#,
+                r#"[{"op": "replace", "path": "/a/0", "value": 3}]"#,
+                r#"{"a":[3,2]}"#,
+            ),
+            (
+                r#"{"a": {"b": 1}, "c": []}"#,
+                r#"[{"op": "move", "from": "/a/b", "path": "/c/0"}]"#,
+                r#"{"a":{},"c":[1]}"#,
+            ),
+            (
+                r#"{"a": {"b": 1}}"#,
+                r#"[{"op": "move", "from": "/a", "path": "/a"}]"#,
+                r#"{"a":{"b":1}}"#,
+            ),
+            (
+                r#"{"a": [1]}"#,
+                r#"[{"op": "copy", "from": "/a", "path": "/b"}]"#,
+                r#"{"a":[1],"b":[1]}"#,
+            ),
+            (
+                r#"{"a": {"x": 1, "y": 2.0}}"#,
+                r#"[{"op": "test", "path": "/a", "value": {"y": 2, "x": 1e0}}]"#,
+                r#"{"a":{"x":1,"y":2.0}}"#,
+            ),
+            (r#"{"a": 1}"#, r#"[{"op": "remove", "path": ""}]"#, "null"),
+        ];
+        for (target, patch, expected) in cases {
+            assert_eq!(apply(target, patch).unwrap(), expected, "{patch}");
+        }
+    }
+
+    #[test]
+    fn fails_without_changing_target() {
+        let mut target = parse(r#"{"a": 1}"#);
+        let patch = parse(concat!(
+            r#"[{"op": "add", "path": "/b", "value": 2},"#,
+            r#" {"op": "test", "path": "/a", "value": "1"}]"#
+        ));
+        let err = JsonPatch::from_value(&patch).unwrap().apply(&mut target).unwrap_err();
+        assert_eq!((err.kind(), err.index(), err.pointer()), (PatchErrorKind::TestFailed, 1, "/a"));
+        assert_eq!(target.to_string(), r#"{"a":1}"#);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let cases = [
+            (
+                r#"[{"op": "add", "path": "/a/b", "value": 1}]"#,
+                PatchErrorKind::PathNotFound,
+                "/a/b",
+            ),
+            (
+                r#"[{"op": "add", "path": "/c/2", "value": 1}]"#,
+                PatchErrorKind::PathNotFound,
+                "/c/2",
+            ),
+            (r#"[{"op": "remove", "path": "/c/01"}]"#, PatchErrorKind::PathNotFound, "/c/01"),
+            (r#"[{"op": "move", "from": "/x", "path": "/y"}]"#, PatchErrorKind::PathNotFound, "/x"),
+            (
+                r#"[{"op": "move", "from": "/c", "path": "/c/0"}]"#,
+                PatchErrorKind::MoveIntoChild,
+                "/c",
+            ),
+            (r#"[{"op": "test", "path": "/z", "value": 1}]"#, PatchErrorKind::PathNotFound, "/z"),
+            (r#"[{"op": "copy", "from": "c", "path": "/y"}]"#, PatchErrorKind::InvalidPointer, "c"),
+            (r#"[{"op": "add", "path": "/y"}]"#, PatchErrorKind::InvalidOperation, "/y"),
+            (r#"[{"op": "frob", "path": "/y"}]"#, PatchErrorKind::InvalidOperation, "/y"),
+            (r#"[{"op": "remove"}]"#, PatchErrorKind::InvalidOperation, ""),
+            (r#"{"op": "remove", "path": "/y"}"#, PatchErrorKind::InvalidOperation, ""),
+        ];
+        for (patch, kind, pointer) in cases {
+            let err = apply(r#"{"c": [1]}"#, patch).unwrap_err();
+            assert_eq!((err.kind(), err.pointer()), (kind, pointer), "{patch}");
+        }
+    }
+
+    #[test]
+    fn diff_turns_one_value_into_another() {
+        let from = parse(r#"{"a": 1, "b": [1, 2, 3], "c": {"d": 1}}"#);
+        let to = parse(r#"{"b": [1, 3, 4], "c": {"d": 1.0}, "e": null}"#);
+        let patch = JsonPatch::diff(&from, &to);
+        assert_eq!(
+            patch.to_value().to_string(),
+            concat!(
+                r#"[{"op":"remove","path":"/a"},{"op":"remove","path":"/b/1"},"#,
+                r#"{"op":"add","path":"/b/2","value":4},{"op":"add","path":"/e","value":null}]"#
+            )
+        );
+        assert_eq!(JsonPatch::from_value(&patch.to_value()), Ok(patch.clone()));
+        let mut patched = from;
+        patch.apply(&mut patched).unwrap();
+        assert!(equivalent(&patched, &to));
+        assert!(JsonPatch::diff(&to, &to).is_empty());
+    }
+
+    #[test]
+    fn merges_rfc_examples() {
+        let cases = [
+            (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+            (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+            (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+            (r#"{"a":{"b":"c"}}"#, r#"{"a":{"b":"d","c":null}}"#, r#"{"a":{"b":"d"}}"#),
+            (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+            (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+            (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+            (r#"{"a":"foo"}"#, "null", "null"),
+            (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+            (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+            (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+            (r#"{}"#, r#"{"a":{"bb":{"ccc":null}}}"#, r#"{"a":{"bb":{}}}"#),
+        ];
+        for (target, patch, expected) in cases {
+            let mut target = parse(target);
+            merge_patch(&mut target, &parse(patch));
+            assert_eq!(target.to_string(), expected, "{patch}");
+        }
+    }
+
+    #[test]
+    fn diffs_merge_patch() {
+        let from = parse(r#"{"a": 1, "b": {"c": [1], "d": 2}, "e": 3.0}"#);
+        let to = parse(r#"{"b": {"c": [1, 2], "d": 2}, "e": 3, "f": {"g": null}}"#);
+        let patch = diff_merge_patch(&from, &to);
+        assert_eq!(patch.to_string(), r#"{"a":null,"b":{"c":[1,2]},"f":{"g":null}}"#);
+        let mut patched = from;
+        merge_patch(&mut patched, &patch);
+        assert_eq!(patched.to_string(), r#"{"b":{"c":[1,2],"d":2},"e":3.0,"f":{}}"#);
+        assert_eq!(diff_merge_patch(&parse("[1]"), &parse("null")), JsonValue::Null);
+    }
+}
@@ -0,0 +1,779 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! JSONPath, as specified by RFC 9535, for selecting the values in a
+//! [`JsonValue`] that match an expression like `$.store.book[?@.price < 10]`,
+//! along with their locations, written as normalized paths like
+//! `$['store']['book'][0]`.
+
+mod extract;
+mod parser;
+mod regex;
+
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    error::Error,
+    fmt::{self, Display, Write},
+    rc::Rc,
+    str::FromStr,
+};
+
+pub use self::extract::{Extract, PathExtractor};
+
+use self::regex::Regex;
+use super::{value::equivalent, JsonValue, Position};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PathErrorKind {
+    UnexpectedEnd,
+    UnexpectedCharacter,
+    /// A string literal has a control character, an escape sequence that is
+    /// not allowed or a lone surrogate.
+    InvalidString,
+    /// A number has a leading zero, or an index is `-0` or outside the range
+    /// of integers that I-JSON can hold exactly.
+    InvalidNumber,
+    UnknownFunction,
+    /// A function is given too few or too many arguments, or one of a type
+    /// it does not take.
+    InvalidArguments,
+    /// A side of a comparison is neither a literal, a query that selects at
+    /// most one node nor a function that returns a value.
+    NotComparable,
+    /// A test is neither a query nor a function that returns a logical
+    /// result.
+    NotTestable,
+    /// A segment cannot be matched from where a value lies alone, as
+    /// [`PathExtractor`] requires, because it has a filter, a negative index
+    /// or a slice that counts from the end or backwards.
+    NotStreamable,
+    /// The literal pattern given to `match` or `search` is a valid I-Regexp
+    /// but uses what is not supported, like a Unicode category other than
+    /// `N`, `Z`, `Zs`, `Zl`, `Zp` and `Cc`.
+    UnsupportedPattern,
+    /// Parentheses, function calls and filter selectors are nested deeper
+    /// than the parser allows.
+    DepthLimitExceeded,
+}
+
+impl Display for PathErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl Error for PathErrorKind {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PathError {
+    kind: PathErrorKind,
+    position: Position,
+}
+
+impl PathError {
+    fn new(kind: PathErrorKind, text: &str, offset: usize) -> Self {
+        let mut position = Position { offset, ..Position::default() };
+        let mut after_cr = false;
+        for c in text[..offset].chars() {
+            match c {
+                '\n' if after_cr => {}
+                '\r' | '\n' => {
+                    position.line += 1;
+                    position.column = 1;
+                }
+                _ => position.column += 1,
+            }
+            after_cr = c == '\r';
+        }
+        Self { kind, position }
+    }
+
+    pub fn kind(&self) -> PathErrorKind {
+        self.kind
+    }
+
+    /// The position in the expression at which parsing gave up.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.position)
+    }
+}
+
+impl Error for PathError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+/// A compiled JSONPath expression.
+#[derive(Debug, Clone)]
+pub struct JsonPath {
+    text: String,
+    query: Query,
+}
+
+impl JsonPath {
+    pub fn parse(text: &str) -> Result<Self, PathError> {
+        let query = parser::Parser::new(text).query()?;
+        Ok(Self { text: text.to_owned(), query })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether the expression selects at most one node, whatever the value,
+    /// because it only has segments with a single name or index.
+    pub fn is_singular(&self) -> bool {
+        self.query.is_singular()
+    }
+
+    /// Whether the expression can be matched from where a value lies alone,
+    /// as [`PathExtractor`] requires, see [`PathErrorKind::NotStreamable`].
+    pub fn is_streamable(&self) -> bool {
+        self.query.segments.iter().all(Segment::is_streamable)
+    }
+
+    /// Returns the nodes selected from a value along with their normalized
+    /// paths, in the order the expression selects them, which may include
+    /// the same node more than once.
+    pub fn query<'a>(&self, value: &'a JsonValue) -> Vec<PathNode<'a>> {
+        let root = Located { value, location: Rc::new(Location::Root) };
+        let nodes = self.query.select(root, value);
+        nodes
+            .into_iter()
+            .map(|node| PathNode { path: node.location.to_string(), value: node.value })
+            .collect()
+    }
+
+    /// Returns the values selected from a value, as with
+    /// [`JsonPath::query`] but without their paths.
+    pub fn select<'a>(&self, value: &'a JsonValue) -> Vec<&'a JsonValue> {
+        self.query.select(value, value)
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        JsonPath::parse(s)
+    }
+}
+
+impl Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// A value selected by a [`JsonPath`] and its location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathNode<'a> {
+    path: String,
+    value: &'a JsonValue,
+}
+
+impl<'a> PathNode<'a> {
+    /// The normalized path of the value, like `$['items'][0]`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn value(&self) -> &'a JsonValue {
+        self.value
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Query {
+    /// Whether the query starts at the root, with `$`, rather than the
+    /// current node, with `@`.
+    absolute: bool,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    /// Where the segment starts in the expression.
+    offset: usize,
+    descendant: bool,
+    selectors: Vec<Selector>,
+}
+
+#[derive(Debug, Clone)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice { start: Option<i64>, end: Option<i64>, step: Option<i64> },
+    Filter(Expr),
+}
+
+/// A logical expression of a filter.
+#[derive(Debug, Clone)]
+enum Expr {
+    Or(Vec<Expr>),
+    And(Vec<Expr>),
+    Not(Box<Expr>),
+    Compare(Comparable, Comparison, Comparable),
+    /// Whether a query selects any nodes.
+    Exists(Query),
+    /// The result of a function that returns a logical result.
+    Test(Function),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// What can be compared, which is a literal, a singular query or a
+/// function that returns a value. It is also what is passed to functions
+/// that take a value.
+#[derive(Debug, Clone)]
+enum Comparable {
+    Literal(JsonValue),
+    Query(Query),
+    Function(Box<Function>),
+}
+
+/// A call to one of the functions defined by RFC 9535. The pattern of
+/// `match` and `search` is compiled once when it is a literal.
+#[derive(Debug, Clone)]
+enum Function {
+    Length(Comparable),
+    Count(Query),
+    Match(Comparable, Comparable, Option<Regex>),
+    Search(Comparable, Comparable, Option<Regex>),
+    Value(Query),
+}
+
+/// A step from a node to one of its children.
+#[derive(Debug, Copy, Clone)]
+enum Step<'a> {
+    Name(&'a str),
+    Index(usize),
+}
+
+/// Where a node lies, held as the step to it from its parent's location.
+#[derive(Debug)]
+enum Location<'a> {
+    Root,
+    Child(Rc<Location<'a>>, Step<'a>),
+}
+
+/// Writes the step as it appears in a normalized path, where names are in
+/// single quotes with the quote, backslash and control characters escaped.
+impl Display for Step<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Step::Index(index) => return write!(f, "[{index}]"),
+            Step::Name(name) => name,
+        };
+        f.write_str("['")?;
+        for c in name.chars() {
+            match c {
+                '\x08' => f.write_str("\\b")?,
+                '\x0C' => f.write_str("\\f")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                '\'' => f.write_str("\\'")?,
+                '\\' => f.write_str("\\\\")?,
+                '\0'..='\x1F' => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_str("']")
+    }
+}
+
+/// Writes the normalized path.
+impl Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Root => f.write_char('$'),
+            Location::Child(parent, step) => write!(f, "{parent}{step}"),
+        }
+    }
+}
+
+/// A node being selected, which is a value and, when its location is
+/// wanted, where it lies.
+trait Node<'a>: Clone {
+    fn value(&self) -> &'a JsonValue;
+    fn child(&self, step: Step<'a>, value: &'a JsonValue) -> Self;
+}
+
+impl<'a> Node<'a> for &'a JsonValue {
+    fn value(&self) -> &'a JsonValue {
+        self
+    }
+
+    fn child(&self, _: Step<'a>, value: &'a JsonValue) -> Self {
+        value
+    }
+}
+
+#[derive(Clone)]
+struct Located<'a> {
+    value: &'a JsonValue,
+    location: Rc<Location<'a>>,
+}
+
+impl<'a> Node<'a> for Located<'a> {
+    fn value(&self) -> &'a JsonValue {
+        self.value
+    }
+
+    fn child(&self, step: Step<'a>, value: &'a JsonValue) -> Self {
+        Located { value, location: Rc::new(Location::Child(self.location.clone(), step)) }
+    }
+}
+
+/// Calls `f` with each child of a node, in order.
+fn for_each_child<'a, N: Node<'a>>(node: &N, mut f: impl FnMut(N)) {
+    match node.value() {
+        JsonValue::Array(array) => {
+            for (index, value) in array.iter().enumerate() {
+                f(node.child(Step::Index(index), value));
+            }
+        }
+        JsonValue::Object(object) => {
+            for (name, value) in object.iter() {
+                f(node.child(Step::Name(name), value));
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Query {
+    fn is_singular(&self) -> bool {
+        self.segments.iter().all(|segment| {
+            !segment.descendant
+                && matches!(segment.selectors[..], [Selector::Name(_) | Selector::Index(_)])
+        })
+    }
+
+    /// Selects nodes, starting at the root or, for a relative query, the
+    /// current node.
+    fn select<'a, N: Node<'a>>(&self, current: N, root: &'a JsonValue) -> Vec<N> {
+        self.segments.iter().fold(vec![current], |nodes, segment| {
+            let mut selected = Vec::new();
+            for node in &nodes {
+                segment.select(node, root, &mut selected);
+            }
+            selected
+        })
+    }
+
+    /// Selects values for a query within a filter.
+    fn values<'a>(&self, current: &'a JsonValue, root: &'a JsonValue) -> Vec<&'a JsonValue> {
+        self.select(if self.absolute { root } else { current }, root)
+    }
+}
+
+impl Segment {
+    fn is_streamable(&self) -> bool {
+        self.selectors.iter().all(|selector| match selector {
+            Selector::Name(_) | Selector::Wildcard => true,
+            Selector::Index(index) => *index >= 0,
+            Selector::Slice { start, end, step } => {
+                start.unwrap_or(0) >= 0
+                    && end.map_or(true, |end| end >= 0)
+                    && step.unwrap_or(1) >= 0
+            }
+            Selector::Filter(_) => false,
+        })
+    }
+
+    /// Whether a streamable segment selects the child at a step.
+    fn selects(&self, step: Step<'_>) -> bool {
+        self.selectors.iter().any(|selector| match (selector, step) {
+            (Selector::Name(name), Step::Name(other)) => name == other,
+            (Selector::Wildcard, _) => true,
+            (Selector::Index(index), Step::Index(other)) => *index == other as i64,
+            (Selector::Slice { start, end, step }, Step::Index(index)) => {
+                let (index, start, step) = (index as i64, start.unwrap_or(0), step.unwrap_or(1));
+                step > 0
+                    && index >= start
+                    && end.map_or(true, |end| index < end)
+                    && (index - start) % step == 0
+            }
+            _ => false,
+        })
+    }
+
+    fn select<'a, N: Node<'a>>(&self, node: &N, root: &'a JsonValue, selected: &mut Vec<N>) {
+        for selector in &self.selectors {
+            selector.select(node, root, selected);
+        }
+        if self.descendant {
+            for_each_child(node, |child| self.select(&child, root, selected));
+        }
+    }
+}
+
+impl Selector {
+    fn select<'a, N: Node<'a>>(&self, node: &N, root: &'a JsonValue, selected: &mut Vec<N>) {
+        match (self, node.value()) {
+            (Selector::Name(name), JsonValue::Object(object)) => {
+                if let Some((name, value)) = object.get_key_value(name) {
+                    selected.push(node.child(Step::Name(name), value));
+                }
+            }
+            (Selector::Wildcard, _) => for_each_child(node, |child| selected.push(child)),
+            (Selector::Index(index), JsonValue::Array(array)) => {
+                let len = array.len() as i64;
+                let index = if *index < 0 { len + index } else { *index };
+                if (0..len).contains(&index) {
+                    let index = index as usize;
+                    selected.push(node.child(Step::Index(index), &array[index]));
+                }
+            }
+            (Selector::Slice { start, end, step }, JsonValue::Array(array)) => {
+                let len = array.len() as i64;
+                let step = step.unwrap_or(1);
+                let normalize = |i: i64| if i < 0 { len + i } else { i };
+                let mut push = |i: i64| {
+                    let i = i as usize;
+                    selected.push(node.child(Step::Index(i), &array[i]));
+                };
+                match step.cmp(&0) {
+                    Ordering::Equal => {}
+                    Ordering::Greater => {
+                        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+                        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+                        let mut i = lower;
+                        while i < upper {
+                            push(i);
+                            i += step;
+                        }
+                    }
+                    Ordering::Less => {
+                        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+                        let lower = normalize(end.unwrap_or(-len - 1)).clamp(-1, len - 1);
+                        let mut i = upper;
+                        while lower < i {
+                            push(i);
+                            i += step;
+                        }
+                    }
+                }
+            }
+            (Selector::Filter(expr), _) => {
+                for_each_child(node, |child| {
+                    if expr.test(child.value(), root) {
+                        selected.push(child);
+                    }
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Expr {
+    fn test(&self, current: &JsonValue, root: &JsonValue) -> bool {
+        match self {
+            Expr::Or(exprs) => exprs.iter().any(|expr| expr.test(current, root)),
+            Expr::And(exprs) => exprs.iter().all(|expr| expr.test(current, root)),
+            Expr::Not(expr) => !expr.test(current, root),
+            Expr::Compare(left, comparison, right) => {
+                let left = left.evaluate(current, root);
+                let right = right.evaluate(current, root);
+                compare(left.as_deref(), *comparison, right.as_deref())
+            }
+            Expr::Exists(query) => !query.values(current, root).is_empty(),
+            Expr::Test(function) => function.test(current, root),
+        }
+    }
+}
+
+/// Compares two values, either of which may be nothing, as when a query
+/// selects no node. Only numbers and strings are ordered, and nothing is
+/// only equal to nothing.
+fn compare(left: Option<&JsonValue>, comparison: Comparison, right: Option<&JsonValue>) -> bool {
+    let equal = || match (left, right) {
+        (Some(left), Some(right)) => equivalent(left, right),
+        (left, right) => left.is_none() && right.is_none(),
+    };
+    let less = |left: Option<&JsonValue>, right: Option<&JsonValue>| match (left, right) {
+        (Some(JsonValue::Number(a)), Some(JsonValue::Number(b))) => {
+            match (a.as_i128(), b.as_i128()) {
+                (Ok(a), Ok(b)) => a < b,
+                _ => matches!((a.as_f64(), b.as_f64()), (Ok(a), Ok(b)) if a < b),
+            }
+        }
+        (Some(JsonValue::String(a)), Some(JsonValue::String(b))) => a < b,
+        _ => false,
+    };
+    match comparison {
+        Comparison::Eq => equal(),
+        Comparison::Ne => !equal(),
+        Comparison::Lt => less(left, right),
+        Comparison::Le => less(left, right) || equal(),
+        Comparison::Gt => less(right, left),
+        Comparison::Ge => less(right, left) || equal(),
+    }
+}
+
+impl Comparable {
+    fn evaluate<'a>(
+        &'a self,
+        current: &'a JsonValue,
+        root: &'a JsonValue,
+    ) -> Option<Cow<'a, JsonValue>> {
+        match self {
+            Comparable::Literal(value) => Some(Cow::Borrowed(value)),
+            Comparable::Query(query) => {
+                query.values(current, root).first().map(|&value| Cow::Borrowed(value))
+            }
+            Comparable::Function(function) => function.evaluate(current, root),
+        }
+    }
+}
+
+impl Function {
+    /// Whether the function returns a logical result rather than a value.
+    fn is_logical(&self) -> bool {
+        matches!(self, Function::Match(..) | Function::Search(..))
+    }
+
+    fn evaluate<'a>(
+        &'a self,
+        current: &'a JsonValue,
+        root: &'a JsonValue,
+    ) -> Option<Cow<'a, JsonValue>> {
+        match self {
+            Function::Length(argument) => {
+                let len = match argument.evaluate(current, root)?.as_ref() {
+                    JsonValue::String(string) => string.chars().count(),
+                    JsonValue::Array(array) => array.len(),
+                    JsonValue::Object(object) => object.len(),
+                    _ => return None,
+                };
+                Some(Cow::Owned(len.into()))
+            }
+            Function::Count(query) => Some(Cow::Owned(query.values(current, root).len().into())),
+            Function::Value(query) => match query.values(current, root)[..] {
+                [value] => Some(Cow::Borrowed(value)),
+                _ => None,
+            },
+            Function::Match(..) | Function::Search(..) => None,
+        }
+    }
+
+    fn test(&self, current: &JsonValue, root: &JsonValue) -> bool {
+        let (Function::Match(text, pattern, regex) | Function::Search(text, pattern, regex)) = self else {
+            return false;
+        };
+        let text = text.evaluate(current, root);
+        let Some(JsonValue::String(text)) = text.as_deref() else {
+            return false;
+        };
+        let regex = match regex {
+            Some(regex) => Cow::Borrowed(regex),
+            None => match pattern.evaluate(current, root).as_deref() {
+                Some(JsonValue::String(pattern)) => match Regex::new(pattern) {
+                    Ok(regex) => Cow::Owned(regex),
+                    Err(_) => return false,
+                },
+                _ => return false,
+            },
+        };
+        match self {
+            Function::Match(..) => regex.is_match(text),
+            _ => regex.is_found(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STORE: &str = r#"{ "store": {
+        "book": [
+          { "category": "reference", "author": "Nigel Rees",
+            "title": "Sayings of the Century", "price": 8.95 },
+          { "category": "fiction", "author": "Evelyn Waugh",
+            "title": "Sword of Honour", "price": 12.99 },
+          { "category": "fiction", "author": "Herman Melville",
+            "title": "Moby Dick", "isbn": "0-553-21311-3", "price": 8.99 },
+          { "category": "fiction", "author": "J. R. R. Tolkien",
+            "title": "The Lord of the Rings", "isbn": "0-395-19395-8",
+            "price": 22.99 }
+        ],
+        "bicycle": { "color": "red", "price": 399 }
+      } }"#;
+
+    fn paths(path: &str, text: &str) -> Vec<String> {
+        let value = JsonValue::parse(text).unwrap();
+        let path = JsonPath::parse(path).unwrap();
+        path.query(&value).into_iter().map(|node| node.path().to_owned()).collect()
+    }
+
+    fn values(path: &str, text: &str) -> String {
+        let value = JsonValue::parse(text).unwrap();
+        let path = JsonPath::parse(path).unwrap();
+        JsonValue::Array(path.select(&value).into_iter().cloned().collect()).to_string()
+    }
+
+    fn error(path: &str) -> PathErrorKind {
+        JsonPath::parse(path).unwrap_err().kind()
+    }
+
+    #[test]
+    fn rfc_9535_bookstore_examples() {
+        assert_eq!(
+            values("$.store.book[*].author", STORE),
+            r#"["Nigel Rees","Evelyn Waugh","Herman Melville","J. R. R. Tolkien"]"#
+        );
+        assert_eq!(values("$..author", STORE), values("$.store.book[*].author", STORE));
+        assert_eq!(values("$.store..price", STORE), "[8.95,12.99,8.99,22.99,399]");
+        assert_eq!(paths("$..book[2]", STORE), ["$['store']['book'][2]"]);
+        assert_eq!(values("$..book[2].author", STORE), r#"["Herman Melville"]"#);
+        assert_eq!(values("$..book[2].publisher", STORE), "[]");
+        assert_eq!(paths("$..book[-1]", STORE), ["$['store']['book'][3]"]);
+        assert_eq!(paths("$..book[:2]", STORE), paths("$..book[0,1]", STORE));
+        assert_eq!(
+            paths("$..book[?@.isbn]", STORE),
+            ["$['store']['book'][2]", "$['store']['book'][3]"]
+        );
+        assert_eq!(
+            values("$..book[?@.price<10].title", STORE),
+            r#"["Sayings of the Century","Moby Dick"]"#
+        );
+        assert_eq!(paths("$..*", STORE).len(), 27);
+    }
+
+    #[test]
+    fn rfc_9535_slice_examples() {
+        let array = r#"["a", "b", "c", "d", "e", "f", "g"]"#;
+        assert_eq!(values("$[1:3]", array), r#"["b","c"]"#);
+        assert_eq!(values("$[5:]", array), r#"["f","g"]"#);
+        assert_eq!(values("$[1:5:2]", array), r#"["b","d"]"#);
+        assert_eq!(values("$[5:1:-2]", array), r#"["f","d"]"#);
+        assert_eq!(values("$[::-1]", array), r#"["g","f","e","d","c","b","a"]"#);
+        assert_eq!(values("$[0:5:0]", array), "[]");
+    }
+
+    #[test]
+    fn rfc_9535_filter_examples() {
+        let value = r#"{"a": [3, 5, 1, 2, 4, 6, {"b": "j"}, {"b": "k"}, {"b": {}}, {"b": "kilo"}],
+                        "o": {"p": 1, "q": 2, "r": 3, "s": 5, "t": {"u": 6}}, "e": "f"}"#;
+        assert_eq!(values("$.a[?@.b == 'kilo']", value), r#"[{"b":"kilo"}]"#);
+        assert_eq!(values("$.a[?(@.b == 'kilo')]", value), r#"[{"b":"kilo"}]"#);
+        assert_eq!(values("$.a[?@>3.5]", value), "[5,4,6]");
+        assert_eq!(values("$.a[?@.b]", value), r#"[{"b":"j"},{"b":"k"},{"b":{}},{"b":"kilo"}]"#);
+        assert_eq!(values("$[?@.*]", value), values("$['a','o']", value));
+        assert_eq!(values("$[?@[?@.b]]", value), values("$['a']", value));
+        assert_eq!(values("$.o[?@<3, ?@<3]", value), "[1,2,1,2]");
+        assert_eq!(values(r#"$.a[?@<2 || @.b == "k"]"#, value), r#"[1,{"b":"k"}]"#);
+        assert_eq!(values("$.a[?match(@.b, '[jk]')]", value), r#"[{"b":"j"},{"b":"k"}]"#);
+        assert_eq!(
+            values("$.a[?search(@.b, '[jk]')]", value),
+            r#"[{"b":"j"},{"b":"k"},{"b":"kilo"}]"#
+        );
+        assert_eq!(values("$.o[?@>1 && @<4]", value), "[2,3]");
+        assert_eq!(values("$.o[?@.u || @.x]", value), r#"[{"u":6}]"#);
+        assert_eq!(values("$.a[?@.b == $.x]", value), "[3,5,1,2,4,6]");
+        assert_eq!(values("$.a[?@ == @]", value).matches(',').count(), 9);
+    }
+
+    #[test]
+    fn rfc_9535_function_examples() {
+        let value = r#"[{"a": "abc", "b": [1, 2, 3]}, {"a": "x", "b": []}, {"a": 12}]"#;
+        assert_eq!(values("$[?length(@.a) == 3].a", value), r#"["abc"]"#);
+        assert_eq!(values("$[?count(@.b[*]) > 1].a", value), r#"["abc"]"#);
+        assert_eq!(values("$[?value(@..a) == 'x'].a", value), r#"["x"]"#);
+        assert_eq!(values("$[?match(@.a, 'a.c')].a", value), r#"["abc"]"#);
+        assert_eq!(values("$[?match(@.a, '(')].a", value), "[]");
+        assert_eq!(error("$[?length(@.*) < 3]"), PathErrorKind::InvalidArguments);
+        assert_eq!(error("$[?count(1) == 1]"), PathErrorKind::InvalidArguments);
+        assert_eq!(error("$[?match(@.a, 'a', 'b')]"), PathErrorKind::InvalidArguments);
+        assert_eq!(error("$[?length(@) == 1 == 1]"), PathErrorKind::UnexpectedCharacter);
+        assert_eq!(error("$[?foo(@)]"), PathErrorKind::UnknownFunction);
+        assert_eq!(error("$[?@.* == 1]"), PathErrorKind::NotComparable);
+        assert_eq!(error("$[?length(@)]"), PathErrorKind::NotTestable);
+    }
+
+    #[test]
+    fn normalized_paths_escape_names() {
+        let value = r#"{"a'b": {"c\\d": [0, {"\u0001": 1}]}}"#;
+        assert_eq!(
+            paths("$..*", value),
+            [
+                r"$['a\'b']",
+                r"$['a\'b']['c\\d']",
+                r"$['a\'b']['c\\d'][0]",
+                r"$['a\'b']['c\\d'][1]",
+                r"$['a\'b']['c\\d'][1]['\u0001']",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_syntax() {
+        assert_eq!(error("$."), PathErrorKind::UnexpectedEnd);
+        assert_eq!(error("$[01]"), PathErrorKind::InvalidNumber);
+        assert_eq!(error("$[-0]"), PathErrorKind::InvalidNumber);
+        assert_eq!(error("$['\t']"), PathErrorKind::InvalidString);
+        assert_eq!(error("$ x"), PathErrorKind::UnexpectedCharacter);
+        let err = JsonPath::parse("$[?@.a\n  && x]").unwrap_err();
+        assert_eq!((err.position().line(), err.position().column()), (2, 6));
+    }
+
+    #[test]
+    fn classifies_queries() {
+        assert!(JsonPath::parse("$.a[0]['b']").unwrap().is_singular());
+        assert!(!JsonPath::parse("$.a[*]").unwrap().is_singular());
+        assert!(JsonPath::parse("$..a[1:3].*").unwrap().is_streamable());
+        assert!(!JsonPath::parse("$.a[-1]").unwrap().is_streamable());
+        assert!(!JsonPath::parse("$.a[?@]").unwrap().is_streamable());
+    }
+
+    #[test]
+    fn rejects_unsupported_patterns_when_parsed() {
+        let err = JsonPath::parse(r"$[?match(@, '\\p{Lu}')]").unwrap_err();
+        assert_eq!(err.kind(), PathErrorKind::UnsupportedPattern);
+        assert_eq!(err.position().column(), 4);
+        assert_eq!(error(r"$[?search(@, '\\p{Nd}+')]"), PathErrorKind::UnsupportedPattern);
+        let nested = format!("$[?match(@, '{}a{}')]", "(".repeat(200), ")".repeat(200));
+        assert_eq!(error(&nested), PathErrorKind::UnsupportedPattern);
+        assert_eq!(values(r"$[?match(@, '\\p{N}+')]", r#"["12", "a"]"#), r#"["12"]"#);
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let parens = |depth| format!("$[?{}@{}]", "(".repeat(depth), ")".repeat(depth));
+        assert!(JsonPath::parse(&parens(99)).is_ok());
+        assert_eq!(error(&parens(100)), PathErrorKind::DepthLimitExceeded);
+        assert_eq!(error(&parens(5000)), PathErrorKind::DepthLimitExceeded);
+        let filters = |depth| format!("$[?{}@{}]", "@[?".repeat(depth), "]".repeat(depth));
+        assert!(JsonPath::parse(&filters(99)).is_ok());
+        assert_eq!(error(&filters(5000)), PathErrorKind::DepthLimitExceeded);
+        let calls = |depth| format!("$[?{}@{} == 1]", "length(".repeat(depth), ")".repeat(depth));
+        assert!(JsonPath::parse(&calls(99)).is_ok());
+        assert_eq!(error(&calls(5000)), PathErrorKind::DepthLimitExceeded);
+        let err = JsonPath::parse(&parens(5000)).unwrap_err();
+        assert_eq!(err.position().column(), 103);
+    }
+}
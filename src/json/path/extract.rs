@@ -0,0 +1,369 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fmt::Write;
+
+use super::{JsonPath, PathError, PathErrorKind, Segment, Step};
+use crate::json::{
+    value::ValueBuilder, DuplicateKeys, JsonToken, JsonTokenKind, JsonValue, ParseError,
+};
+
+/// Finds the values matched by any of a set of [`JsonPath`] expressions in
+/// tokens as they are read, by a [`crate::json::JsonTextReader`],
+/// [`crate::json::JsonStreamReader`] or [`crate::json::JsonPushParser`],
+/// yielding each with its normalized path as soon as its last token is
+/// read. Only the values being matched are held in memory, and arrays and
+/// objects that cannot hold a match are skipped.
+///
+/// A value is yielded once however many expressions match it, and values
+/// are yielded in the order they end, so one nested in another that is
+/// also matched comes first. The expressions must be streamable, see
+/// [`JsonPath::is_streamable`].
+pub struct PathExtractor<'s> {
+    patterns: Vec<Vec<Segment>>,
+    duplicate_keys: DuplicateKeys,
+    /// The arrays and objects being read that may hold a match.
+    frames: Vec<Frame>,
+    /// The values being matched, the innermost last.
+    captures: Vec<Capture<'s>>,
+    /// The normalized path of the array or object on top of `frames`.
+    path: String,
+    /// How deep the reader is in an array or object that cannot hold a
+    /// match, where 0 is not in one.
+    skipping: usize,
+}
+
+struct Frame {
+    /// The index of each expression that may match within the array or
+    /// object along with the number of its segments matched so far.
+    states: Vec<(usize, usize)>,
+    /// The length of `path` at the parent of the array or object.
+    parent_len: usize,
+    /// The index of the next element, for an array.
+    index: usize,
+    /// The name of the member whose value is read next, for an object.
+    name: Option<String>,
+}
+
+struct Capture<'s> {
+    path: String,
+    builder: ValueBuilder<'s, JsonValue>,
+}
+
+impl<'s> PathExtractor<'s> {
+    /// Creates an extractor for the values that follow in the tokens it is
+    /// given, with members by the same name handled as by
+    /// [`crate::json::ReaderOptions::duplicate_keys`]. Fails with
+    /// [`PathErrorKind::NotStreamable`] at the first segment of any
+    /// expression that is not streamable.
+    pub fn new<'p>(
+        paths: impl IntoIterator<Item = &'p JsonPath>,
+        duplicate_keys: DuplicateKeys,
+    ) -> Result<Self, PathError> {
+        let mut patterns = Vec::new();
+        for path in paths {
+            let segments = &path.query.segments;
+            if let Some(segment) = segments.iter().find(|segment| !segment.is_streamable()) {
+                return Err(PathError::new(
+                    PathErrorKind::NotStreamable,
+                    &path.text,
+                    segment.offset,
+                ));
+            }
+            patterns.push(segments.clone());
+        }
+        Ok(Self {
+            patterns,
+            duplicate_keys,
+            frames: Vec::new(),
+            captures: Vec::new(),
+            path: String::new(),
+            skipping: 0,
+        })
+    }
+
+    /// Matches tokens, all of them at once or a batch at a time as they are
+    /// read, yielding the values that end in them, see
+    /// [`PathExtractor::push`].
+    pub fn extract<I>(&mut self, tokens: I) -> Extract<'_, 's, I::IntoIter>
+    where
+        I: IntoIterator,
+    {
+        Extract { extractor: self, tokens: tokens.into_iter() }
+    }
+
+    /// Matches the next token, returning the value that it ends, if any,
+    /// with its normalized path. Tokens must be pushed in the order they
+    /// are read, including any values that follow at the top level.
+    pub fn push(
+        &mut self,
+        token: &JsonToken<'s>,
+    ) -> Result<Option<(String, JsonValue)>, ParseError> {
+        let kind = token.kind();
+        if self.skipping > 0 {
+            match kind {
+                JsonTokenKind::ArrayStart | JsonTokenKind::ObjectStart => self.skipping += 1,
+                JsonTokenKind::ArrayEnd | JsonTokenKind::ObjectEnd => self.skipping -= 1,
+                _ => {}
+            }
+            return self.capture(token);
+        }
+        match kind {
+            JsonTokenKind::Comment | JsonTokenKind::Whitespace => return Ok(None),
+            JsonTokenKind::ObjectMember => {
+                if let Some(frame) = self.frames.last_mut() {
+                    frame.name = Some(token.as_str()?.into_owned());
+                }
+                return self.capture(token);
+            }
+            JsonTokenKind::ArrayEnd | JsonTokenKind::ObjectEnd => {
+                if let Some(frame) = self.frames.pop() {
+                    self.path.truncate(frame.parent_len);
+                }
+                return self.capture(token);
+            }
+            _ => {}
+        }
+        //
+        // The token starts a value, so work out where it lies and which
+        // expressions it matches or may hold matches for.
+        //
+        let parent_len = self.path.len();
+        let states = match self.frames.last_mut() {
+            None => {
+                self.path.clear();
+                self.path.push('$');
+                (0..self.patterns.len()).map(|pattern| (pattern, 0)).collect()
+            }
+            Some(frame) => {
+                let name = frame.name.take();
+                let step = match &name {
+                    Some(name) => Step::Name(name),
+                    None => {
+                        frame.index += 1;
+                        Step::Index(frame.index - 1)
+                    }
+                };
+                let _ = write!(self.path, "{step}");
+                let mut states = Vec::new();
+                for &(pattern, matched) in &frame.states {
+                    let segment = &self.patterns[pattern][matched];
+                    if segment.selects(step) && !states.contains(&(pattern, matched + 1)) {
+                        states.push((pattern, matched + 1));
+                    }
+                    if segment.descendant && !states.contains(&(pattern, matched)) {
+                        states.push((pattern, matched));
+                    }
+                }
+                states
+            }
+        };
+        if states.iter().any(|&(pattern, matched)| matched == self.patterns[pattern].len()) {
+            let path = self.path.clone();
+            let builder = ValueBuilder::new(self.duplicate_keys);
+            self.captures.push(Capture { path, builder });
+        }
+        let states: Vec<_> = states
+            .into_iter()
+            .filter(|&(pattern, matched)| matched < self.patterns[pattern].len())
+            .collect();
+        match kind {
+            JsonTokenKind::ArrayStart | JsonTokenKind::ObjectStart if !states.is_empty() => {
+                self.frames.push(Frame { states, parent_len, index: 0, name: None });
+            }
+            JsonTokenKind::ArrayStart | JsonTokenKind::ObjectStart => {
+                self.path.truncate(parent_len);
+                self.skipping = 1;
+            }
+            _ => self.path.truncate(parent_len),
+        }
+        self.capture(token)
+    }
+
+    /// Adds a token to the values being matched, returning the innermost
+    /// once it is complete.
+    fn capture(
+        &mut self,
+        token: &JsonToken<'s>,
+    ) -> Result<Option<(String, JsonValue)>, ParseError> {
+        let Some((innermost, outer)) = self.captures.split_last_mut() else {
+            return Ok(None);
+        };
+        //
+        // Each value being matched holds those that started after it, so a
+        // token can only complete the innermost one, as when `$..a` matches
+        // both `{"a":1}` and the `1` in it.
+        //
+        for capture in outer {
+            let completed = capture.builder.push(token)?;
+            debug_assert!(completed.is_none(), "a value ended before one it holds");
+        }
+        let Some(value) = innermost.builder.push(token)? else {
+            return Ok(None);
+        };
+        let capture = self.captures.pop().expect("the innermost value");
+        Ok(Some((capture.path, value)))
+    }
+}
+
+/// An iterator over the values matched in tokens, returned by
+/// [`PathExtractor::extract`].
+pub struct Extract<'a, 's, I> {
+    extractor: &'a mut PathExtractor<'s>,
+    tokens: I,
+}
+
+impl<'s, I, E> Iterator for Extract<'_, 's, I>
+where
+    I: Iterator<Item = Result<JsonToken<'s>, E>>,
+    E: From<ParseError>,
+{
+    type Item = Result<(String, JsonValue), E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let token = match self.tokens.next()? {
+                Ok(token) => token,
+                Err(err) => return Some(Err(err)),
+            };
+            match self.extractor.push(&token) {
+                Ok(None) => {}
+                Ok(Some(node)) => return Some(Ok(node)),
+                Err(err) => return Some(Err(err.into())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{JsonPushParser, JsonStreamReader, JsonTextReader, ReaderOptions};
+
+    const TEXT: &str = r#"{"a": {"a": 1, "b": [true, {"a": null}]}, "c": [1, 2, 3], "d": "x"}"#;
+
+    fn parse(paths: &[&str]) -> Vec<JsonPath> {
+        paths.iter().map(|path| JsonPath::parse(path).unwrap()).collect()
+    }
+
+    fn extract(paths: &[&str], text: &str) -> Vec<(String, String)> {
+        let paths = parse(paths);
+        let mut extractor = PathExtractor::new(&paths, DuplicateKeys::LastWins).unwrap();
+        let tokens = &mut JsonTextReader::new(text);
+        extractor
+            .extract(tokens)
+            .map(|node| node.map(|(path, value)| (path, value.to_string())))
+            .collect::<Result<_, ParseError>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn yields_nested_matches_first() {
+        assert_eq!(
+            extract(&["$..a"], r#"{"a":{"a":1}}"#),
+            [
+                (String::from("$['a']['a']"), String::from("1")),
+                (String::from("$['a']"), String::from(r#"{"a":1}"#)),
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_like_query() {
+        let value = JsonValue::parse(TEXT).unwrap();
+        for path in ["$..a", "$..*", "$.c[1:]", "$['a','d']", "$.a.b[1].a", "$[*][0]"] {
+            let mut expected: Vec<_> = JsonPath::parse(path)
+                .unwrap()
+                .query(&value)
+                .into_iter()
+                .map(|node| (node.path().to_owned(), node.value().to_string()))
+                .collect();
+            let mut actual = extract(&[path], TEXT);
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected, "{path}");
+        }
+    }
+
+    #[test]
+    fn yields_a_value_once_for_several_paths() {
+        let nodes = extract(&["$.c[0]", "$.c[:1]", "$..[0]"], TEXT);
+        let paths: Vec<_> = nodes.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["$['a']['b'][0]", "$['c'][0]"]);
+    }
+
+    #[test]
+    fn reads_values_that_follow_at_top_level() {
+        let options = ReaderOptions::default().trailing_content(true);
+        let paths = parse(&["$[0]"]);
+        let mut extractor = PathExtractor::new(&paths, DuplicateKeys::LastWins).unwrap();
+        let tokens = &mut JsonTextReader::with_options("[1] [2]", options);
+        let values: Vec<_> = extractor.extract(tokens).map(|node| node.unwrap().1).collect();
+        assert_eq!(values, [JsonValue::from(1)]);
+    }
+
+    #[test]
+    fn handles_duplicate_keys_as_given() {
+        let paths = parse(&["$.a"]);
+        let text = r#"{"a": {"b": 1, "b": 2}}"#;
+        for (duplicate_keys, expected) in
+            [(DuplicateKeys::FirstWins, r#"{"b":1}"#), (DuplicateKeys::LastWins, r#"{"b":2}"#)]
+        {
+            let mut extractor = PathExtractor::new(&paths, duplicate_keys).unwrap();
+            let tokens = &mut JsonTextReader::new(text);
+            let (_, value) = extractor.extract(tokens).next().unwrap().unwrap();
+            assert_eq!(value.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn reads_from_stream_reader() {
+        let paths = parse(&["$.a.b[*]"]);
+        let mut extractor = PathExtractor::new(&paths, DuplicateKeys::LastWins).unwrap();
+        let reader = JsonStreamReader::new(TEXT.as_bytes());
+        let nodes: Vec<_> = extractor.extract(reader).map(|node| node.unwrap().0).collect();
+        assert_eq!(nodes, ["$['a']['b'][0]", "$['a']['b'][1]"]);
+    }
+
+    #[test]
+    fn reads_from_push_parser_a_byte_at_a_time() {
+        let paths = parse(&["$..a"]);
+        let mut extractor = PathExtractor::new(&paths, DuplicateKeys::LastWins).unwrap();
+        let mut parser = JsonPushParser::new();
+        let mut nodes = Vec::new();
+        for byte in TEXT.as_bytes() {
+            for node in extractor.extract(parser.feed(&[*byte])) {
+                nodes.push(node.unwrap());
+            }
+        }
+        for node in extractor.extract(parser.finish()) {
+            nodes.push(node.unwrap());
+        }
+        let nodes: Vec<_> =
+            nodes.into_iter().map(|(path, value)| (path, value.to_string())).collect();
+        assert_eq!(nodes, extract(&["$..a"], TEXT));
+        assert_eq!(nodes.len(), 3);
+    }
+
+    #[test]
+    fn passes_on_read_errors() {
+        let paths = parse(&["$[*]"]);
+        let mut extractor = PathExtractor::new(&paths, DuplicateKeys::LastWins).unwrap();
+        let mut nodes = extractor.extract(JsonStreamReader::new(&b"[1, }"[..]));
+        assert_eq!(nodes.next().unwrap().unwrap().1, JsonValue::from(1));
+        assert!(nodes.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn rejects_paths_that_are_not_streamable() {
+        let paths = parse(&["$.a", "$.b[-1]"]);
+        let Err(err) = PathExtractor::new(&paths, DuplicateKeys::LastWins) else {
+            panic!("{:?}", paths[1]);
+        };
+        assert_eq!(err.kind(), PathErrorKind::NotStreamable);
+        assert_eq!(err.position().column(), 4);
+    }
+}
@@ -0,0 +1,514 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use super::{
+    regex::{Regex, RegexError},
+    Comparable, Comparison, Expr, Function, PathError, PathErrorKind, Query, Segment, Selector,
+};
+use crate::json::{unescape, JsonNumber, JsonValue};
+
+/// The largest magnitude of an index, which is that of the integers I-JSON
+/// can hold exactly.
+const MAX_INDEX: i64 = (1 << 53) - 1;
+
+/// The most parentheses, function calls and filter selectors that may be
+/// nested in one another, which bounds the recursion of the parser.
+const MAX_NESTING: usize = 100;
+
+/// An operand of a comparison or an argument to a function, before it is
+/// known which of the two it is.
+enum Operand {
+    Literal(JsonValue),
+    Query(Query),
+    Function(Function),
+}
+
+/// An argument to a function.
+enum Argument {
+    Operand(Operand),
+    Logical(Expr),
+}
+
+/// A recursive-descent parser for the grammar of RFC 9535, which reports
+/// where the expression is not well-formed or not well-typed.
+pub(super) struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+    /// The number of parentheses, function calls and filter selectors open.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self { text, offset: 0, depth: 0 }
+    }
+
+    fn error(&self, kind: PathErrorKind) -> PathError {
+        PathError::new(kind, self.text, self.offset)
+    }
+
+    /// Returns the error for the next character, or for the end.
+    fn unexpected(&self) -> PathError {
+        self.error(match self.peek() {
+            Some(_) => PathErrorKind::UnexpectedCharacter,
+            None => PathErrorKind::UnexpectedEnd,
+        })
+    }
+
+    /// Parses what is nested in the parentheses or filter selector that
+    /// start at the next character, failing there if that goes deeper than
+    /// [`MAX_NESTING`].
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, PathError>,
+    ) -> Result<T, PathError> {
+        if self.depth == MAX_NESTING {
+            return Err(self.error(PathErrorKind::DepthLimitExceeded));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.offset..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.offset += c.len_utf8();
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), PathError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.offset += 1;
+        }
+    }
+
+    /// Parses the whole of the text as a query starting at the root.
+    pub fn query(mut self) -> Result<Query, PathError> {
+        self.expect('$')?;
+        let query = Query { absolute: true, segments: self.segments()? };
+        match self.peek() {
+            None => Ok(query),
+            Some(_) => Err(self.unexpected()),
+        }
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>, PathError> {
+        let mut segments = Vec::new();
+        loop {
+            let offset = self.offset;
+            self.skip_blank();
+            match self.peek() {
+                Some('.' | '[') => segments.push(self.segment()?),
+                _ => {
+                    self.offset = offset;
+                    return Ok(segments);
+                }
+            }
+        }
+    }
+
+    fn segment(&mut self) -> Result<Segment, PathError> {
+        let offset = self.offset;
+        if !self.eat('.') {
+            return Ok(Segment { offset, descendant: false, selectors: self.bracketed()? });
+        }
+        let descendant = self.eat('.');
+        let selectors = match self.peek() {
+            Some('[') if descendant => self.bracketed()?,
+            Some('*') => {
+                self.offset += 1;
+                vec![Selector::Wildcard]
+            }
+            _ => vec![Selector::Name(self.member_name()?)],
+        };
+        Ok(Segment { offset, descendant, selectors })
+    }
+
+    fn member_name(&mut self) -> Result<String, PathError> {
+        let is_first = |c: char| c.is_ascii_alphabetic() || c == '_' || c >= '\u{80}';
+        let start = self.offset;
+        match self.peek() {
+            Some(c) if is_first(c) => self.offset += c.len_utf8(),
+            _ => return Err(self.unexpected()),
+        }
+        while let Some(c) = self.peek().filter(|&c| is_first(c) || c.is_ascii_digit()) {
+            self.offset += c.len_utf8();
+        }
+        Ok(self.text[start..self.offset].to_owned())
+    }
+
+    fn bracketed(&mut self) -> Result<Vec<Selector>, PathError> {
+        self.expect('[')?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_blank();
+            selectors.push(self.selector()?);
+            self.skip_blank();
+            if !self.eat(',') {
+                self.expect(']')?;
+                return Ok(selectors);
+            }
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, PathError> {
+        match self.peek() {
+            Some('\'' | '"') => return Ok(Selector::Name(self.string()?)),
+            Some('*') => {
+                self.offset += 1;
+                return Ok(Selector::Wildcard);
+            }
+            Some('?') => {
+                let expr = self.nested(|parser| {
+                    parser.offset += 1;
+                    parser.skip_blank();
+                    parser.or()
+                })?;
+                return Ok(Selector::Filter(expr));
+            }
+            _ => {}
+        }
+        let start = self.index()?;
+        self.skip_blank();
+        if !self.eat(':') {
+            return match start {
+                Some(index) => Ok(Selector::Index(index)),
+                None => Err(self.unexpected()),
+            };
+        }
+        self.skip_blank();
+        let end = self.index()?;
+        self.skip_blank();
+        let step = if self.eat(':') {
+            self.skip_blank();
+            self.index()?
+        } else {
+            None
+        };
+        Ok(Selector::Slice { start, end, step })
+    }
+
+    /// Parses an integer for an index or slice, if there is one.
+    fn index(&mut self) -> Result<Option<i64>, PathError> {
+        if !matches!(self.peek(), Some('-' | '0'..='9')) {
+            return Ok(None);
+        }
+        let start = self.offset;
+        self.eat('-');
+        let digits = self.offset;
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.offset += 1;
+        }
+        let text = &self.text[start..self.offset];
+        let valid = match &self.text[digits..self.offset] {
+            "" => false,
+            "0" => start == digits,
+            digits => !digits.starts_with('0'),
+        };
+        match text.parse::<i64>() {
+            Ok(index) if valid && (-MAX_INDEX..=MAX_INDEX).contains(&index) => Ok(Some(index)),
+            _ => Err(PathError::new(PathErrorKind::InvalidNumber, self.text, start)),
+        }
+    }
+
+    /// Parses a string literal in single or double quotes.
+    fn string(&mut self) -> Result<String, PathError> {
+        let quote = self.peek().ok_or_else(|| self.unexpected())?;
+        self.offset += 1;
+        let start = self.offset;
+        let mut escaped = false;
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.unexpected());
+            };
+            match c {
+                _ if escaped => {
+                    if !matches!(c, 'b' | 'f' | 'n' | 'r' | 't' | '/' | '\\' | 'u') && c != quote {
+                        return Err(self.error(PathErrorKind::InvalidString));
+                    }
+                    escaped = false;
+                }
+                '\\' => escaped = true,
+                '\0'..='\x1F' => return Err(self.error(PathErrorKind::InvalidString)),
+                _ if c == quote => break,
+                _ => {}
+            }
+            self.offset += c.len_utf8();
+        }
+        let text = &self.text[start..self.offset];
+        self.offset += 1;
+        unescape(text).map_err(|(offset, _)| {
+            PathError::new(PathErrorKind::InvalidString, self.text, start + offset)
+        })
+    }
+
+    fn or(&mut self) -> Result<Expr, PathError> {
+        let mut exprs = vec![self.and()?];
+        loop {
+            self.skip_blank();
+            if !self.text[self.offset..].starts_with("||") {
+                break;
+            }
+            self.offset += 2;
+            self.skip_blank();
+            exprs.push(self.and()?);
+        }
+        Ok(if exprs.len() == 1 { exprs.remove(0) } else { Expr::Or(exprs) })
+    }
+
+    fn and(&mut self) -> Result<Expr, PathError> {
+        let mut exprs = vec![self.basic()?];
+        loop {
+            self.skip_blank();
+            if !self.text[self.offset..].starts_with("&&") {
+                break;
+            }
+            self.offset += 2;
+            self.skip_blank();
+            exprs.push(self.basic()?);
+        }
+        Ok(if exprs.len() == 1 { exprs.remove(0) } else { Expr::And(exprs) })
+    }
+
+    /// Parses a parenthesized expression, a comparison or a test, where
+    /// only the first and last may be negated.
+    fn basic(&mut self) -> Result<Expr, PathError> {
+        let negated = self.eat('!');
+        if negated {
+            self.skip_blank();
+        }
+        if self.peek() == Some('(') {
+            let expr = self.nested(|parser| {
+                parser.offset += 1;
+                parser.skip_blank();
+                let expr = parser.or()?;
+                parser.skip_blank();
+                parser.expect(')')?;
+                Ok(expr)
+            })?;
+            return Ok(if negated { Expr::Not(Box::new(expr)) } else { expr });
+        }
+        let start = self.offset;
+        let operand = self.operand()?;
+        if !negated {
+            let offset = self.offset;
+            self.skip_blank();
+            if let Some(comparison) = self.comparison() {
+                let left = self.comparable(operand, start)?;
+                self.skip_blank();
+                let start = self.offset;
+                let right = self.operand()?;
+                let right = self.comparable(right, start)?;
+                return Ok(Expr::Compare(left, comparison, right));
+            }
+            self.offset = offset;
+        }
+        let expr = match operand {
+            Operand::Query(query) => Expr::Exists(query),
+            Operand::Function(function) if function.is_logical() => Expr::Test(function),
+            _ => return Err(PathError::new(PathErrorKind::NotTestable, self.text, start)),
+        };
+        Ok(if negated { Expr::Not(Box::new(expr)) } else { expr })
+    }
+
+    fn comparison(&mut self) -> Option<Comparison> {
+        let (comparison, len) = match self.text.get(self.offset..self.offset + 2) {
+            Some("==") => (Comparison::Eq, 2),
+            Some("!=") => (Comparison::Ne, 2),
+            Some("<=") => (Comparison::Le, 2),
+            Some(">=") => (Comparison::Ge, 2),
+            _ => match self.peek()? {
+                '<' => (Comparison::Lt, 1),
+                '>' => (Comparison::Gt, 1),
+                _ => return None,
+            },
+        };
+        self.offset += len;
+        Some(comparison)
+    }
+
+    /// Checks that an operand starting at an offset can be compared or
+    /// passed as a value.
+    fn comparable(&self, operand: Operand, start: usize) -> Result<Comparable, PathError> {
+        match operand {
+            Operand::Literal(value) => Ok(Comparable::Literal(value)),
+            Operand::Query(query) if query.is_singular() => Ok(Comparable::Query(query)),
+            Operand::Function(function) if !function.is_logical() => {
+                Ok(Comparable::Function(Box::new(function)))
+            }
+            _ => Err(PathError::new(PathErrorKind::NotComparable, self.text, start)),
+        }
+    }
+
+    /// Parses a literal, a query or a function.
+    fn operand(&mut self) -> Result<Operand, PathError> {
+        match self.peek() {
+            Some('$') => {
+                self.offset += 1;
+                Ok(Operand::Query(Query { absolute: true, segments: self.segments()? }))
+            }
+            Some('@') => {
+                self.offset += 1;
+                Ok(Operand::Query(Query { absolute: false, segments: self.segments()? }))
+            }
+            Some('\'' | '"') => Ok(Operand::Literal(JsonValue::String(self.string()?))),
+            Some('-' | '0'..='9') => Ok(Operand::Literal(self.number()?)),
+            Some('a'..='z') => {
+                let start = self.offset;
+                while matches!(self.peek(), Some('a'..='z' | '0'..='9' | '_')) {
+                    self.offset += 1;
+                }
+                let name = &self.text[start..self.offset];
+                if self.peek() == Some('(') {
+                    return Ok(Operand::Function(self.function(name, start)?));
+                }
+                match name {
+                    "true" => Ok(Operand::Literal(JsonValue::Bool(true))),
+                    "false" => Ok(Operand::Literal(JsonValue::Bool(false))),
+                    "null" => Ok(Operand::Literal(JsonValue::Null)),
+                    _ => {
+                        self.offset = start;
+                        Err(self.unexpected())
+                    }
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn number(&mut self) -> Result<JsonValue, PathError> {
+        fn digits(parser: &mut Parser<'_>) -> usize {
+            let start = parser.offset;
+            while matches!(parser.peek(), Some('0'..='9')) {
+                parser.offset += 1;
+            }
+            parser.offset - start
+        }
+
+        let start = self.offset;
+        self.eat('-');
+        let leading_zero = self.peek() == Some('0');
+        let mut valid = match digits(self) {
+            0 => false,
+            n => n == 1 || !leading_zero,
+        };
+        if self.eat('.') {
+            valid &= digits(self) > 0;
+        }
+        if self.eat('e') || self.eat('E') {
+            let _ = self.eat('-') || self.eat('+');
+            valid &= digits(self) > 0;
+        }
+        match self.text[start..self.offset].parse::<JsonNumber>() {
+            Ok(number) if valid => Ok(JsonValue::Number(number)),
+            _ => Err(PathError::new(PathErrorKind::InvalidNumber, self.text, start)),
+        }
+    }
+
+    /// Parses the arguments of a function, whose name starts at an offset,
+    /// and checks that they are of the types it takes.
+    fn function(&mut self, name: &str, start: usize) -> Result<Function, PathError> {
+        let arguments = self.nested(|parser| {
+            parser.expect('(')?;
+            parser.skip_blank();
+            let mut arguments = Vec::new();
+            if parser.eat(')') {
+                return Ok(arguments);
+            }
+            loop {
+                arguments.push(parser.argument()?);
+                parser.skip_blank();
+                if !parser.eat(',') {
+                    parser.expect(')')?;
+                    return Ok(arguments);
+                }
+                parser.skip_blank();
+            }
+        })?;
+        let invalid = || PathError::new(PathErrorKind::InvalidArguments, self.text, start);
+        let value = |argument: Argument| match argument {
+            Argument::Operand(operand) => self.comparable(operand, start).map_err(|_| invalid()),
+            Argument::Logical(_) => Err(invalid()),
+        };
+        let nodes = |argument: Argument| match argument {
+            Argument::Operand(Operand::Query(query)) => Ok(query),
+            _ => Err(invalid()),
+        };
+        let mut arguments = arguments.into_iter();
+        let function = match name {
+            "length" => Function::Length(value(arguments.next().ok_or_else(invalid)?)?),
+            "count" => Function::Count(nodes(arguments.next().ok_or_else(invalid)?)?),
+            "value" => Function::Value(nodes(arguments.next().ok_or_else(invalid)?)?),
+            "match" | "search" => {
+                let text = value(arguments.next().ok_or_else(invalid)?)?;
+                let pattern = value(arguments.next().ok_or_else(invalid)?)?;
+                //
+                // A pattern that is not a valid I-Regexp makes the function
+                // false, as RFC 9535 specifies, but one that is valid yet
+                // cannot be run is an error rather than quietly false.
+                //
+                let regex = match &pattern {
+                    Comparable::Literal(JsonValue::String(pattern)) => match Regex::new(pattern) {
+                        Ok(regex) => Some(regex),
+                        Err(RegexError::Invalid) => None,
+                        Err(RegexError::Unsupported) => {
+                            let kind = PathErrorKind::UnsupportedPattern;
+                            return Err(PathError::new(kind, self.text, start));
+                        }
+                    },
+                    _ => None,
+                };
+                match name {
+                    "match" => Function::Match(text, pattern, regex),
+                    _ => Function::Search(text, pattern, regex),
+                }
+            }
+            _ => return Err(PathError::new(PathErrorKind::UnknownFunction, self.text, start)),
+        };
+        match arguments.next() {
+            None => Ok(function),
+            Some(_) => Err(invalid()),
+        }
+    }
+
+    /// Parses an argument, which is a literal, query or function when that
+    /// is all there is to it, and otherwise a logical expression.
+    fn argument(&mut self) -> Result<Argument, PathError> {
+        let start = self.offset;
+        //
+        // Anything but a negation or parenthesized expression starts with an
+        // operand, so an error reading it would only recur when read again
+        // as a logical expression, and doing so for each of nested function
+        // calls would take time exponential in their depth.
+        //
+        if !matches!(self.peek(), Some('!' | '(')) {
+            let operand = self.operand()?;
+            let offset = self.offset;
+            self.skip_blank();
+            if matches!(self.peek(), Some(',' | ')')) {
+                self.offset = offset;
+                return Ok(Argument::Operand(operand));
+            }
+            self.offset = start;
+        }
+        Ok(Argument::Logical(self.or()?))
+    }
+}
@@ -0,0 +1,519 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! I-Regexp, as specified by RFC 9485, for the `match` and `search`
+//! functions of JSONPath. Expressions are compiled to a program that is run
+//! by simulating all its threads in step, so matching takes time linear in
+//! the length of the text, whatever the expression.
+//!
+//! Of the Unicode categories, only those that the standard library can
+//! decide exactly are supported, namely `N`, `Z`, `Zs`, `Zl`, `Zp` and `Cc`.
+//! An expression that uses any other is [`RegexError::Unsupported`].
+
+/// The most instructions that an expression may compile to, which bounds
+/// how far ranges like `{1000}` can expand.
+const MAX_PROGRAM_SIZE: usize = 10_000;
+
+/// The most groups that may be nested in one another, which bounds the
+/// recursion of the parser.
+const MAX_NESTING: usize = 100;
+
+/// The categories that I-Regexp allows besides those supported.
+const UNSUPPORTED_CATEGORIES: &[&str] = &[
+    "L", "Lu", "Ll", "Lt", "Lm", "Lo", "M", "Mn", "Mc", "Me", "Nd", "Nl", "No", "P", "Pc", "Pd",
+    "Ps", "Pe", "Pi", "Pf", "Po", "S", "Sm", "Sc", "Sk", "So", "C", "Cf", "Co", "Cn",
+];
+
+/// Why an expression could not be compiled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) enum RegexError {
+    /// The expression is not a valid I-Regexp.
+    Invalid,
+    /// The expression is valid but uses a category that is not supported,
+    /// nests groups deeper than [`MAX_NESTING`] or compiles to more than
+    /// [`MAX_PROGRAM_SIZE`] instructions.
+    Unsupported,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Category {
+    Number,
+    Separator,
+    SpaceSeparator,
+    LineSeparator,
+    ParagraphSeparator,
+    Control,
+}
+
+impl Category {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "N" => Category::Number,
+            "Z" => Category::Separator,
+            "Zs" => Category::SpaceSeparator,
+            "Zl" => Category::LineSeparator,
+            "Zp" => Category::ParagraphSeparator,
+            "Cc" => Category::Control,
+            _ => return None,
+        })
+    }
+
+    fn contains(self, c: char) -> bool {
+        match self {
+            Category::Number => c.is_numeric(),
+            Category::Separator => c.is_whitespace() && !c.is_control(),
+            Category::SpaceSeparator => {
+                c.is_whitespace() && !c.is_control() && !matches!(c, '\u{2028}' | '\u{2029}')
+            }
+            Category::LineSeparator => c == '\u{2028}',
+            Category::ParagraphSeparator => c == '\u{2029}',
+            Category::Control => c.is_control(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ClassItem {
+    Range(char, char),
+    Category(Category, bool),
+}
+
+/// A set of characters that matches one character of the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Class {
+    /// Any character but CR and LF, which is what `.` matches.
+    Dot,
+    Char(char),
+    Set {
+        negated: bool,
+        items: Vec<ClassItem>,
+    },
+}
+
+impl Class {
+    fn contains(&self, c: char) -> bool {
+        match self {
+            Class::Dot => !matches!(c, '\n' | '\r'),
+            Class::Char(ch) => c == *ch,
+            Class::Set { negated, items } => {
+                let found = items.iter().any(|item| match *item {
+                    ClassItem::Range(first, last) => (first..=last).contains(&c),
+                    ClassItem::Category(category, negated) => category.contains(c) != negated,
+                });
+                found != *negated
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Class(Class),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat { node: Box<Node>, min: usize, max: Option<usize> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inst {
+    Class(Class),
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// A compiled I-Regexp.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Regex {
+    program: Vec<Inst>,
+}
+
+impl Regex {
+    /// Compiles an expression.
+    pub fn new(pattern: &str) -> Result<Self, RegexError> {
+        let mut parser =
+            Parser { chars: pattern.chars().collect(), index: 0, depth: 0, unsupported: false };
+        let node = match parser.alternation() {
+            Some(node) if parser.index == parser.chars.len() => node,
+            _ if parser.unsupported => return Err(RegexError::Unsupported),
+            _ => return Err(RegexError::Invalid),
+        };
+        let mut program = Vec::new();
+        compile(&node, &mut program).ok_or(RegexError::Unsupported)?;
+        program.push(Inst::Match);
+        Ok(Self { program })
+    }
+
+    /// Whether the expression matches the whole of the text.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.run(text, false)
+    }
+
+    /// Whether the expression matches some part of the text.
+    pub fn is_found(&self, text: &str) -> bool {
+        self.run(text, true)
+    }
+
+    fn run(&self, text: &str, search: bool) -> bool {
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        self.add(&mut current, 0);
+        for c in text.chars() {
+            if search && current.contains_match(&self.program) {
+                return true;
+            }
+            next.clear();
+            for &pc in &current.list {
+                if let Inst::Class(class) = &self.program[pc] {
+                    if class.contains(c) {
+                        self.add(&mut next, pc + 1);
+                    }
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+            if search {
+                self.add(&mut current, 0);
+            } else if current.list.is_empty() {
+                return false;
+            }
+        }
+        current.contains_match(&self.program)
+    }
+
+    /// Adds the thread at an instruction, following any jumps and splits to
+    /// the instructions that consume a character or match.
+    fn add(&self, threads: &mut Threads, pc: usize) {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if threads.seen[pc] {
+                continue;
+            }
+            threads.seen[pc] = true;
+            match self.program[pc] {
+                Inst::Jump(to) => stack.push(to),
+                Inst::Split(first, second) => {
+                    stack.push(second);
+                    stack.push(first);
+                }
+                Inst::Class(_) | Inst::Match => threads.list.push(pc),
+            }
+        }
+    }
+}
+
+struct Threads {
+    list: Vec<usize>,
+    seen: Vec<bool>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Self { list: Vec::new(), seen: vec![false; len] }
+    }
+
+    fn clear(&mut self) {
+        self.list.clear();
+        self.seen.iter_mut().for_each(|seen| *seen = false);
+    }
+
+    fn contains_match(&self, program: &[Inst]) -> bool {
+        self.list.iter().any(|&pc| program[pc] == Inst::Match)
+    }
+}
+
+fn compile(node: &Node, program: &mut Vec<Inst>) -> Option<()> {
+    if program.len() > MAX_PROGRAM_SIZE {
+        return None;
+    }
+    match node {
+        Node::Class(class) => program.push(Inst::Class(class.clone())),
+        Node::Concat(nodes) => nodes.iter().try_for_each(|node| compile(node, program))?,
+        Node::Alternate(nodes) => {
+            let mut jumps = Vec::new();
+            for (i, node) in nodes.iter().enumerate() {
+                if i + 1 < nodes.len() {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(node, program)?;
+                    jumps.push(program.len());
+                    program.push(Inst::Jump(0));
+                    program[split] = Inst::Split(split + 1, program.len());
+                } else {
+                    compile(node, program)?;
+                }
+            }
+            let end = program.len();
+            jumps.into_iter().for_each(|jump| program[jump] = Inst::Jump(end));
+        }
+        Node::Repeat { node, min, max } => {
+            for _ in 0..*min {
+                compile(node, program)?;
+            }
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(node, program)?;
+                    program.push(Inst::Jump(split));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(program.len() + 1, 0));
+                        compile(node, program)?;
+                    }
+                    let end = program.len();
+                    splits
+                        .into_iter()
+                        .for_each(|split| program[split] = Inst::Split(split + 1, end));
+                }
+            }
+        }
+    }
+    (program.len() <= MAX_PROGRAM_SIZE).then_some(())
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    /// The number of groups open.
+    depth: usize,
+    /// Whether parsing stopped at something valid but not supported.
+    unsupported: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += 1;
+        Some(c)
+    }
+
+    fn alternation(&mut self) -> Option<Node> {
+        let mut branches = vec![self.branch()?];
+        while self.eat('|') {
+            branches.push(self.branch()?);
+        }
+        Some(if branches.len() == 1 { branches.pop()? } else { Node::Alternate(branches) })
+    }
+
+    fn branch(&mut self) -> Option<Node> {
+        let mut pieces = Vec::new();
+        while !matches!(self.peek(), None | Some('|' | ')')) {
+            let node = self.atom()?;
+            pieces.push(self.quantifier(node)?);
+        }
+        Some(Node::Concat(pieces))
+    }
+
+    fn quantifier(&mut self, node: Node) -> Option<Node> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.index += 1;
+                let min = self.count()?;
+                let max = if self.eat(',') {
+                    match self.peek() {
+                        Some('}') => None,
+                        _ => Some(self.count()?),
+                    }
+                } else {
+                    Some(min)
+                };
+                if !matches!(self.peek(), Some('}')) || max.map_or(false, |max| max < min) {
+                    return None;
+                }
+                (min, max)
+            }
+            _ => return Some(node),
+        };
+        self.index += 1;
+        Some(Node::Repeat { node: Box::new(node), min, max })
+    }
+
+    fn count(&mut self) -> Option<usize> {
+        let start = self.index;
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.index += 1;
+        }
+        self.chars[start..self.index].iter().collect::<String>().parse().ok()
+    }
+
+    fn atom(&mut self) -> Option<Node> {
+        let class = match self.next()? {
+            '(' => {
+                if self.depth == MAX_NESTING {
+                    self.unsupported = true;
+                    return None;
+                }
+                self.depth += 1;
+                let node = self.alternation()?;
+                self.depth -= 1;
+                return self.eat(')').then_some(node);
+            }
+            '.' => Class::Dot,
+            '[' => self.class_expr()?,
+            '\\' => match self.escape()? {
+                ClassItem::Range(c, _) => Class::Char(c),
+                item => Class::Set { negated: false, items: vec![item] },
+            },
+            ')' | '*' | '+' | '?' | ']' | '{' | '|' | '}' => return None,
+            c => Class::Char(c),
+        };
+        Some(Node::Class(class))
+    }
+
+    /// Parses what follows a `\`, either a single character escape as a
+    /// range of one or a category escape.
+    fn escape(&mut self) -> Option<ClassItem> {
+        let c = match self.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            c @ ('(' | ')' | '*' | '+' | '-' | '.' | '?' | '[' | '\\' | ']' | '^' | '{' | '|'
+            | '}') => c,
+            c @ ('p' | 'P') => {
+                if !self.eat('{') {
+                    return None;
+                }
+                let start = self.index;
+                while !matches!(self.peek(), None | Some('}')) {
+                    self.index += 1;
+                }
+                let name: String = self.chars[start..self.index].iter().collect();
+                if !self.eat('}') {
+                    return None;
+                }
+                let Some(category) = Category::from_name(&name) else {
+                    self.unsupported = UNSUPPORTED_CATEGORIES.contains(&name.as_str());
+                    return None;
+                };
+                return Some(ClassItem::Category(category, c == 'P'));
+            }
+            _ => return None,
+        };
+        Some(ClassItem::Range(c, c))
+    }
+
+    /// Parses what follows the `[` of a character class expression.
+    fn class_expr(&mut self) -> Option<Class> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        if self.eat('-') {
+            items.push(ClassItem::Range('-', '-'));
+        }
+        loop {
+            let item = match self.next()? {
+                ']' if !items.is_empty() => return Some(Class::Set { negated, items }),
+                '-' if self.peek() == Some(']') => ClassItem::Range('-', '-'),
+                '[' | ']' | '-' => return None,
+                '\\' => self.escape()?,
+                c => ClassItem::Range(c, c),
+            };
+            let item = match item {
+                ClassItem::Range(first, _) if self.peek() == Some('-') => {
+                    if self.chars.get(self.index + 1) == Some(&']') {
+                        item
+                    } else {
+                        self.index += 1;
+                        let last = match self.next()? {
+                            '\\' => match self.escape()? {
+                                ClassItem::Range(c, _) => c,
+                                ClassItem::Category(..) => return None,
+                            },
+                            '[' | ']' | '-' => return None,
+                            c => c,
+                        };
+                        if last < first {
+                            return None;
+                        }
+                        ClassItem::Range(first, last)
+                    }
+                }
+                item => item,
+            };
+            items.push(item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(pattern: &str, text: &str) -> bool {
+        Regex::new(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn matches_whole_text() {
+        assert!(is_match("a(b|c)*d", "abcbd"));
+        assert!(!is_match("a(b|c)*d", "abed"));
+        assert!(!is_match("b", "abc"));
+        assert!(is_match("[^a-c]{2,3}", "xyz"));
+        assert!(!is_match("[^a-c]{2,3}", "xaz"));
+        assert!(is_match("a.c", "a\u{1F600}c"));
+        assert!(!is_match("a.c", "a\nc"));
+    }
+
+    #[test]
+    fn finds_part_of_text() {
+        let regex = Regex::new("b+").unwrap();
+        assert!(regex.is_found("abbc"));
+        assert!(!regex.is_found("ac"));
+        assert!(Regex::new("").unwrap().is_found("anything"));
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for pattern in ["(", "a)", "*", "a{2,1}", "[b-a]", r"\w", "[]", "a**"] {
+            assert_eq!(Regex::new(pattern), Err(RegexError::Invalid), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn matches_supported_categories_exactly() {
+        assert!(is_match(r"\p{N}+", "1\u{0663}\u{00BD}\u{2167}"));
+        assert!(is_match(r"\p{Zs}\p{Zl}\p{Zp}", "\u{3000}\u{2028}\u{2029}"));
+        assert!(!is_match(r"\p{Zs}", "\t"));
+        assert!(is_match(r"\p{Cc}\P{Cc}", "\u{7}a"));
+        assert!(is_match(r"[\p{Z}x]+", " x\u{A0}"));
+    }
+
+    #[test]
+    fn rejects_unsupported_categories() {
+        for category in ["L", "Lu", "Ll", "Nd", "P", "Cn"] {
+            let pattern = format!(r"\p{{{category}}}");
+            assert_eq!(Regex::new(&pattern), Err(RegexError::Unsupported), "{pattern}");
+        }
+        assert_eq!(Regex::new(r"\p{Xx}"), Err(RegexError::Invalid));
+    }
+
+    #[test]
+    fn limits_nesting_and_size() {
+        let nested = |depth| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(is_match(&nested(MAX_NESTING), "a"));
+        assert_eq!(Regex::new(&nested(MAX_NESTING + 1)), Err(RegexError::Unsupported));
+        assert_eq!(Regex::new(&nested(100_000)), Err(RegexError::Unsupported));
+        assert_eq!(Regex::new("(a{100}){200}"), Err(RegexError::Unsupported));
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! JSON Pointer, as specified by RFC 6901.
+
+use std::borrow::Cow;
+
+use super::JsonValue;
+
+/// Appends a reference token to a pointer, escaping `~` as `~0` and `/` as
+/// `~1`.
+pub(super) fn push(pointer: &mut String, token: &str) {
+    pointer.push('/');
+    for ch in token.chars() {
+        match ch {
+            '~' => pointer.push_str("~0"),
+            '/' => pointer.push_str("~1"),
+            ch => pointer.push(ch),
+        }
+    }
+}
+
+/// Splits a pointer into its reference tokens, unescaping `~1` and `~0`.
+/// Returns `None` if the pointer is neither empty nor starts with `/`, or
+/// has a `~` that is not followed by `0` or `1`.
+pub(super) fn split(pointer: &str) -> Option<Vec<Cow<'_, str>>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    pointer
+        .strip_prefix('/')?
+        .split('/')
+        .map(|token| {
+            if !token.contains('~') {
+                return Some(Cow::Borrowed(token));
+            }
+            let mut unescaped = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(ch) = chars.next() {
+                unescaped.push(match ch {
+                    '~' => match chars.next()? {
+                        '0' => '~',
+                        '1' => '/',
+                        _ => return None,
+                    },
+                    ch => ch,
+                });
+            }
+            Some(Cow::Owned(unescaped))
+        })
+        .collect()
+}
+
+/// Reads a reference token as an array index, which is a decimal number
+/// without leading zeros.
+pub(super) fn index(token: &str) -> Option<usize> {
+    match token.as_bytes() {
+        [b'0'] => Some(0),
+        [b'1'..=b'9', rest @ ..] if rest.iter().all(u8::is_ascii_digit) => token.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Looks up a value by the reference tokens of a pointer.
+pub(super) fn get<'v>(value: &'v JsonValue, tokens: &[Cow<'_, str>]) -> Option<&'v JsonValue> {
+    tokens.iter().try_fold(value, |value, token| match value {
+        JsonValue::Object(object) => object.get(token),
+        JsonValue::Array(items) => items.get(index(token)?),
+        _ => None,
+    })
+}
+
+pub(super) fn get_mut<'v>(
+    value: &'v mut JsonValue,
+    tokens: &[Cow<'_, str>],
+) -> Option<&'v mut JsonValue> {
+    tokens.iter().try_fold(value, |value, token| match value {
+        JsonValue::Object(object) => object.get_mut(token),
+        JsonValue::Array(items) => items.get_mut(index(token)?),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_rfc_examples() {
+        let value = JsonValue::parse(
+            r#"{"foo": ["bar", "baz"], "": 0, "a/b": 1, "c%d": 2, "e^f": 3, "g|h": 4,
+                "i\\j": 5, "k\"l": 6, " ": 7, "m~n": 8}"#,
+        )
+        .unwrap();
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(value.pointer("/foo/0"), Some(&JsonValue::from("bar")));
+        for (pointer, expected) in [
+            ("/", 0),
+            ("/a~1b", 1),
+            ("/c%d", 2),
+            ("/e^f", 3),
+            ("/g|h", 4),
+            ("/i\\j", 5),
+            ("/k\"l", 6),
+            ("/ ", 7),
+            ("/m~0n", 8),
+        ] {
+            assert_eq!(value.pointer(pointer), Some(&JsonValue::from(expected)), "{pointer}");
+        }
+        for pointer in ["foo", "/foo/00", "/foo/-", "/foo/2", "/foo/0/x", "/m~2n", "/m~"] {
+            assert_eq!(value.pointer(pointer), None, "{pointer}");
+        }
+    }
+
+    #[test]
+    fn escapes_reference_tokens() {
+        let mut pointer = String::new();
+        push(&mut pointer, "a/b");
+        push(&mut pointer, "~1");
+        assert_eq!(pointer, "/a~1b/~01");
+        assert_eq!(split(&pointer).unwrap(), ["a/b", "~1"]);
+        let mut value = JsonValue::parse(r#"{"a/b": {"~1": [1]}}"#).unwrap();
+        *value.pointer_mut("/a~1b/~01/0").unwrap() = JsonValue::from(2);
+        assert_eq!(value.to_string(), r#"{"a/b":{"~1":[2]}}"#);
+    }
+}
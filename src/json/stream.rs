@@ -0,0 +1,433 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::{borrow::Cow, error::Error, fmt::Display, io};
+
+use super::{Encoding, JsonTextReader, JsonToken, ParseError, ReaderOptions, Step, Tail};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Io(err) => err.fmt(f),
+            StreamError::Parse(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for StreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StreamError::Io(err) => Some(err),
+            StreamError::Parse(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(err: io::Error) -> Self {
+        StreamError::Io(err)
+    }
+}
+
+impl From<ParseError> for StreamError {
+    fn from(err: ParseError) -> Self {
+        StreamError::Parse(err)
+    }
+}
+
+/// A reader that is pushed input as it arrives, in chunks of any size,
+/// rather than pulling it from a source. After each [`JsonPushParser::feed`],
+/// the tokens completed by the input so far can be read. A token that is cut
+/// short by the end of a chunk, like a string, number, comment or even a
+/// `=>` delimiter, is completed once enough input arrives, without scanning
+/// again what was already scanned of it. Call [`JsonPushParser::finish`] at
+/// the end of the input to read any final token.
+///
+/// The encoding of the input is detected from its first bytes, as with
+/// [`Encoding::detect`]. Input in UTF-16 or UTF-32 is transcoded to UTF-8
+/// and positions are then relative to the transcoded text.
+#[derive(Debug)]
+pub struct JsonPushParser {
+    reader: JsonTextReader<'static>,
+    /// Input fed but not yet decoded, which is either too short to detect
+    /// the encoding or at most the start of a sequence split by the end of
+    /// a chunk.
+    pending: Vec<u8>,
+    encoding: Option<Encoding>,
+    needs_input: bool,
+    finished: bool,
+}
+
+impl Default for JsonPushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonPushParser {
+    pub fn new() -> Self {
+        Self::with_options(ReaderOptions::default())
+    }
+
+    pub fn with_options(options: ReaderOptions) -> Self {
+        let reader = JsonTextReader::with_source(Cow::Owned(String::new()), Tail::More, options);
+        Self { reader, pending: Vec::new(), encoding: None, needs_input: true, finished: false }
+    }
+
+    pub fn options(&self) -> &ReaderOptions {
+        self.reader.options()
+    }
+
+    /// The number of arrays and objects that enclose the last token read, as
+    /// with [`JsonTextReader::depth`].
+    pub fn depth(&self) -> usize {
+        self.reader.depth()
+    }
+
+    /// The JSON Pointer to the value that the last token read is part of, as
+    /// with [`JsonTextReader::current_pointer`].
+    pub fn current_pointer(&self) -> String {
+        self.reader.current_pointer()
+    }
+
+    /// The encoding of the input, once enough of it was fed to detect it.
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    /// Whether reading stopped because the input fed so far ends before the
+    /// end of the value.
+    pub fn needs_input(&self) -> bool {
+        self.needs_input
+    }
+
+    /// Appends a chunk of input and returns an iterator over the tokens that
+    /// it completes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called after [`JsonPushParser::finish`].
+    pub fn feed(&mut self, bytes: &[u8]) -> PushTokens<'_> {
+        assert!(!self.finished, "input was already finished");
+        self.compact();
+        if self.reader.tail == Tail::More {
+            self.pending.extend_from_slice(bytes);
+            self.decode();
+        }
+        PushTokens { parser: self }
+    }
+
+    /// Signals the end of the input and returns an iterator over the
+    /// remaining tokens.
+    pub fn finish(&mut self) -> PushTokens<'_> {
+        self.finished = true;
+        if self.reader.tail == Tail::More {
+            self.decode();
+        }
+        if self.reader.tail == Tail::More {
+            self.reader.tail = if self.pending.is_empty() { Tail::End } else { Tail::Invalid };
+        }
+        PushTokens { parser: self }
+    }
+
+    /// Reads the next token, borrowing its text from the internal buffer
+    /// until the following call. Returns `None` at the end of the value or
+    /// when more input is needed to complete the token, which can be told
+    /// apart using [`JsonPushParser::needs_input`].
+    pub fn next_token(&mut self) -> Option<Result<JsonToken<'_>, ParseError>> {
+        self.needs_input = false;
+        match self.reader.read() {
+            Step::Token(lexeme) => Some(Ok(self.reader.borrow_token(lexeme))),
+            Step::Error(err) => Some(Err(err)),
+            Step::End => None,
+            Step::NeedMore => {
+                self.needs_input = true;
+                None
+            }
+        }
+    }
+
+    /// Discards the consumed part of the buffer once it makes up at least
+    /// half of it, so the cost of moving what remains is amortized.
+    fn compact(&mut self) {
+        let reader = &mut self.reader;
+        let consumed = reader.consumed() - reader.base;
+        if consumed > 0 && consumed * 2 >= reader.source.len() {
+            reader.source.to_mut().drain(..consumed);
+            reader.base += consumed;
+        }
+    }
+
+    /// Decodes as much of the pending input as possible to the source of
+    /// the reader, detecting the encoding first if not yet done.
+    fn decode(&mut self) {
+        let encoding = match self.encoding {
+            Some(encoding) => encoding,
+            None => match Encoding::sniff(&self.pending, self.finished) {
+                Some(encoding) => {
+                    self.encoding = Some(encoding);
+                    self.reader.encoding = encoding;
+                    encoding
+                }
+                None => return,
+            },
+        };
+        let (decoded, invalid) = encoding.decode(&self.pending, self.reader.source.to_mut());
+        self.pending.drain(..decoded);
+        if invalid {
+            self.reader.tail = Tail::Invalid;
+        }
+    }
+}
+
+/// An iterator over the tokens completed by the input pushed to a
+/// [`JsonPushParser`].
+#[derive(Debug)]
+pub struct PushTokens<'a> {
+    parser: &'a mut JsonPushParser,
+}
+
+impl Iterator for PushTokens<'_> {
+    type Item = Result<JsonToken<'static>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parser.next_token().map(|result| result.map(JsonToken::into_owned))
+    }
+}
+
+/// Reads JSON text from an [`io::Read`], holding no more of it in memory
+/// than the token being read plus a chunk of input. There is no need to
+/// wrap the input in an [`io::BufReader`] as reading is done in chunks.
+///
+/// Tokens can be read either borrowed from the internal buffer, using
+/// [`JsonStreamReader::next_token`], or owned, by iterating the reader.
+/// Positions are relative to the start of the input.
+#[derive(Debug)]
+pub struct JsonStreamReader<R> {
+    inner: R,
+    parser: JsonPushParser,
+    chunk: Vec<u8>,
+}
+
+impl<R: io::Read> JsonStreamReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self::with_options(inner, ReaderOptions::default())
+    }
+
+    pub fn with_options(inner: R, options: ReaderOptions) -> Self {
+        Self { inner, parser: JsonPushParser::with_options(options), chunk: vec![0; CHUNK_SIZE] }
+    }
+
+    pub fn options(&self) -> &ReaderOptions {
+        self.parser.options()
+    }
+
+    /// The number of arrays and objects that enclose the last token read, as
+    /// with [`JsonTextReader::depth`].
+    pub fn depth(&self) -> usize {
+        self.parser.depth()
+    }
+
+    /// The JSON Pointer to the value that the last token read is part of, as
+    /// with [`JsonTextReader::current_pointer`].
+    pub fn current_pointer(&self) -> String {
+        self.parser.current_pointer()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads the next token, borrowing its text from the internal buffer
+    /// until the following call.
+    pub fn next_token(&mut self) -> Option<Result<JsonToken<'_>, StreamError>> {
+        loop {
+            match self.parser.reader.read() {
+                Step::Token(lexeme) => return Some(Ok(self.parser.reader.borrow_token(lexeme))),
+                Step::Error(err) => return Some(Err(err.into())),
+                Step::End => return None,
+                Step::NeedMore => {
+                    if let Err(err) = self.fill() {
+                        return Some(Err(err.into()));
+                    }
+                }
+            }
+        }
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let read = loop {
+            match self.inner.read(&mut self.chunk) {
+                Ok(read) => break read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        };
+        if read == 0 {
+            self.parser.finish();
+        } else {
+            self.parser.feed(&self.chunk[..read]);
+        }
+        Ok(())
+    }
+}
+
+impl<R: io::Read> Iterator for JsonStreamReader<R> {
+    type Item = Result<JsonToken<'static>, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().map(|result| result.map(JsonToken::into_owned))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{JsonTokenKind, Span, SyntaxError};
+
+    const TEXT: &str =
+        "{ \"name\": \"caf\\u00e9 \u{e9}\", // note\n  'list' => [1, -2.5e3, true, null] }";
+
+    type Tokens = Vec<(JsonTokenKind, String, Span)>;
+
+    fn summarize<'s, E>(
+        tokens: impl IntoIterator<Item = Result<JsonToken<'s>, E>>,
+    ) -> Result<Tokens, E> {
+        tokens
+            .into_iter()
+            .map(|token| token.map(|token| (token.kind(), token.text().to_owned(), token.span())))
+            .collect()
+    }
+
+    fn expected(text: &str, options: ReaderOptions) -> Tokens {
+        summarize(JsonTextReader::with_options(text, options)).unwrap()
+    }
+
+    /// Reads a byte at a time, with an interruption before each.
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+        interrupted: bool,
+    }
+
+    impl io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupted = !self.interrupted;
+            if self.interrupted {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            let Some((first, rest)) = self.bytes.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.bytes = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn stream_reader_reads_like_text_reader() {
+        let options = ReaderOptions::default().comment_tokens(true);
+        let reader = JsonStreamReader::with_options(TEXT.as_bytes(), options);
+        assert_eq!(summarize(reader).unwrap(), expected(TEXT, options));
+        let trickle = Trickle { bytes: TEXT.as_bytes(), interrupted: false };
+        let reader = JsonStreamReader::with_options(trickle, options);
+        assert_eq!(summarize(reader).unwrap(), expected(TEXT, options));
+    }
+
+    #[test]
+    fn stream_reader_reads_long_input() {
+        let text = format!("[{}\"{}\"]", "1, ".repeat(CHUNK_SIZE), "x".repeat(3 * CHUNK_SIZE));
+        let reader = JsonStreamReader::new(text.as_bytes());
+        assert_eq!(summarize(reader).unwrap(), expected(&text, ReaderOptions::default()));
+    }
+
+    #[test]
+    fn stream_reader_lends_tokens() {
+        let mut reader = JsonStreamReader::new(&b"[\"a\", 2]"[..]);
+        let mut tokens = Vec::new();
+        while let Some(token) = reader.next_token() {
+            let text = token.unwrap().text().to_owned();
+            tokens.push((text, reader.depth()));
+        }
+        let tokens: Vec<_> = tokens.iter().map(|(text, depth)| (text.as_str(), *depth)).collect();
+        assert_eq!(tokens, [("[", 0), ("\"a\"", 1), ("2", 1), ("]", 0)]);
+    }
+
+    #[test]
+    fn stream_reader_reports_errors() {
+        struct Failing;
+        impl io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+        }
+        let mut reader = JsonStreamReader::new(Failing);
+        assert!(matches!(reader.next(), Some(Err(StreamError::Io(_)))));
+        let mut reader = JsonStreamReader::new(&b"[1,"[..]);
+        let errors: Vec<_> = reader.by_ref().filter_map(Result::err).collect();
+        let [StreamError::Parse(err)] = &errors[..] else { panic!("{errors:?}") };
+        assert_eq!(err.kind(), SyntaxError::UnterminatedArray);
+    }
+
+    fn push(chunks: &[&[u8]], options: ReaderOptions) -> Result<Tokens, ParseError> {
+        let mut parser = JsonPushParser::with_options(options);
+        let mut tokens = Vec::new();
+        for chunk in chunks {
+            tokens.extend(summarize(parser.feed(chunk))?);
+        }
+        tokens.extend(summarize(parser.finish())?);
+        Ok(tokens)
+    }
+
+    #[test]
+    fn push_parser_reads_chunks_split_anywhere() {
+        let options = ReaderOptions::default().comment_tokens(true);
+        let text = format!("{TEXT} /* end */ ");
+        let bytes = text.as_bytes();
+        let expected = expected(&text, options);
+        for i in 0..=bytes.len() {
+            let (first, second) = bytes.split_at(i);
+            assert_eq!(push(&[first, second], options).unwrap(), expected, "split at {i}");
+        }
+        let chunks: Vec<_> = bytes.chunks(1).collect();
+        assert_eq!(push(&chunks, options).unwrap(), expected);
+    }
+
+    #[test]
+    fn push_parser_completes_last_token_on_finish() {
+        let mut parser = JsonPushParser::new();
+        assert_eq!(summarize(parser.feed(b"12")).unwrap(), []);
+        assert!(parser.needs_input());
+        let tokens = summarize(parser.finish()).unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].1, "12");
+        assert!(!parser.needs_input());
+    }
+
+    #[test]
+    fn push_parser_reports_truncated_input() {
+        let err = push(&[b"[\"ab"], ReaderOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), SyntaxError::UnterminatedString);
+        let err = push(&[b"[1, 2", b"]  x"], ReaderOptions::strict()).unwrap_err();
+        assert_eq!((err.kind(), err.position().column()), (SyntaxError::TrailingContent, 9));
+    }
+
+    #[test]
+    #[should_panic(expected = "input was already finished")]
+    fn push_parser_rejects_input_after_finish() {
+        let mut parser = JsonPushParser::new();
+        parser.finish().for_each(drop);
+        parser.feed(b"1").for_each(drop);
+    }
+}
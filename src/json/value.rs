@@ -0,0 +1,650 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::{
+    fmt::{self, Display},
+    io,
+    ops::{Index, IndexMut},
+    str::FromStr,
+};
+
+use super::{
+    number, pointer, writer, DuplicateKeys, JsonObject, JsonTextReader, JsonTextWriter, JsonToken,
+    JsonTokenKind, NumberError, ParseError, ReaderOptions, SyntaxError, WriteError,
+};
+
+/// A JSON number, held as text in the decimal form specified by RFC 8259 so
+/// that no digits are lost until it is converted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JsonNumber(pub(super) String);
+
+impl JsonNumber {
+    /// Converts a finite floating-point number, returning `None` for `NaN`
+    /// and the infinities, which have no JSON form.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        value.is_finite().then(|| JsonNumber(format!("{value:?}")))
+    }
+
+    fn from_token(token: &JsonToken<'_>) -> Result<Self, ParseError> {
+        match token.as_decimal_str() {
+            Ok(text) => Ok(JsonNumber(text.into_owned())),
+            Err(_) => Err(ParseError::new(SyntaxError::InvalidNumber, token.span().start())),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// See [`JsonToken::as_i128`].
+    pub fn as_i128(&self) -> Result<i128, NumberError> {
+        number::to_i128(&self.0)
+    }
+
+    /// See [`JsonToken::as_i128`].
+    pub fn as_i64(&self) -> Result<i64, NumberError> {
+        self.as_i128().and_then(|n| i64::try_from(n).map_err(|_| NumberError::Overflow))
+    }
+
+    /// See [`JsonToken::as_i128`].
+    pub fn as_u64(&self) -> Result<u64, NumberError> {
+        self.as_i128().and_then(|n| u64::try_from(n).map_err(|_| NumberError::Overflow))
+    }
+
+    /// See [`JsonToken::as_f64`].
+    pub fn as_f64(&self) -> Result<f64, NumberError> {
+        number::to_f64(&self.0)
+    }
+}
+
+/// Parses any number form accepted by [`ReaderOptions::lenient_numbers`],
+/// rewriting it in decimal form as with [`JsonToken::as_decimal_str`].
+impl FromStr for JsonNumber {
+    type Err = NumberError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !number::is_lenient_number(s) {
+            return Err(NumberError::NotANumber);
+        }
+        number::to_decimal_str(s).map(|text| JsonNumber(text.into_owned()))
+    }
+}
+
+impl Display for JsonNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for JsonNumber {
+                fn from(value: $t) -> Self {
+                    JsonNumber(value.to_string())
+                }
+            }
+
+            impl From<$t> for JsonValue {
+                fn from(value: $t) -> Self {
+                    JsonValue::Number(value.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// A JSON value held in memory, as a tree.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum JsonValue {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(JsonNumber),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(JsonObject),
+}
+
+/// A tree of JSON values that a [`ValueBuilder`] can build from the tokens
+/// of text with the lifetime `'s`.
+pub(super) trait Tree<'s>: Sized {
+    /// The name of an object member.
+    type Name;
+    /// An object, to which members are added as they are read.
+    type Object: Default;
+
+    /// Makes a null, boolean, number or string from its token.
+    fn scalar(token: &JsonToken<'s>) -> Result<Self, ParseError>;
+    /// Makes the name of an object member from its token.
+    fn name(token: &JsonToken<'s>) -> Result<Self::Name, ParseError>;
+    fn array(items: Vec<Self>) -> Self;
+    fn object(object: Self::Object) -> Self;
+    /// Adds a member as read, following the policy for duplicate names.
+    fn add(object: &mut Self::Object, name: Self::Name, value: Self, duplicate_keys: DuplicateKeys);
+}
+
+/// An object or array being built, with the name of the member whose value
+/// is to be read next in the case of an object.
+enum Frame<'s, T: Tree<'s>> {
+    Array(Vec<T>),
+    Object(T::Object, Option<T::Name>),
+}
+
+/// Builds a value from its tokens as they are read by someone else.
+pub(super) struct ValueBuilder<'s, T: Tree<'s>> {
+    duplicate_keys: DuplicateKeys,
+    stack: Vec<Frame<'s, T>>,
+}
+
+impl<'s, T: Tree<'s>> ValueBuilder<'s, T> {
+    pub fn new(duplicate_keys: DuplicateKeys) -> Self {
+        Self { duplicate_keys, stack: Vec::new() }
+    }
+
+    /// Adds the next token, returning the value once it is complete.
+    /// Comments and whitespace are ignored.
+    pub fn push(&mut self, token: &JsonToken<'s>) -> Result<Option<T>, ParseError> {
+        let value = match token.kind() {
+            JsonTokenKind::Null
+            | JsonTokenKind::True
+            | JsonTokenKind::False
+            | JsonTokenKind::Number
+            | JsonTokenKind::String => T::scalar(token)?,
+            JsonTokenKind::ArrayStart => {
+                self.stack.push(Frame::Array(Vec::new()));
+                return Ok(None);
+            }
+            JsonTokenKind::ObjectStart => {
+                self.stack.push(Frame::Object(T::Object::default(), None));
+                return Ok(None);
+            }
+            JsonTokenKind::ObjectMember => {
+                if let Some(Frame::Object(_, name)) = self.stack.last_mut() {
+                    *name = Some(T::name(token)?);
+                }
+                return Ok(None);
+            }
+            JsonTokenKind::Comment | JsonTokenKind::Whitespace => return Ok(None),
+            JsonTokenKind::ArrayEnd | JsonTokenKind::ObjectEnd => match self.stack.pop() {
+                Some(Frame::Array(items)) => T::array(items),
+                Some(Frame::Object(object, _)) => T::object(object),
+                None => {
+                    return Err(ParseError::new(SyntaxError::MissingValue, token.span().start()))
+                }
+            },
+        };
+        match self.stack.last_mut() {
+            None => return Ok(Some(value)),
+            Some(Frame::Array(items)) => items.push(value),
+            Some(Frame::Object(object, name)) => {
+                let name = name.take().expect("a member name precedes its value");
+                T::add(object, name, value, self.duplicate_keys);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads the next value from a reader, see [`JsonValue::from_reader`].
+    pub fn read(reader: &mut JsonTextReader<'s>) -> Result<T, ParseError> {
+        let mut builder = Self::new(reader.options().duplicate_keys);
+        loop {
+            let Some(token) = Iterator::next(reader) else {
+                let position = JsonTextReader::position(reader);
+                return Err(ParseError::new(SyntaxError::MissingValue, position));
+            };
+            if let Some(value) = builder.push(&token?)? {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Reads a value from JSON text to its end, see
+    /// [`JsonValue::parse_with_options`].
+    pub fn parse(text: &'s str, options: ReaderOptions) -> Result<T, ParseError> {
+        let mut reader = JsonTextReader::with_options(text, options);
+        let value = Self::read(&mut reader)?;
+        reader.try_for_each(|token| token.map(drop))?;
+        Ok(value)
+    }
+}
+
+impl<'s> Tree<'s> for JsonValue {
+    type Name = String;
+    type Object = JsonObject;
+
+    fn scalar(token: &JsonToken<'s>) -> Result<Self, ParseError> {
+        Ok(match token.kind() {
+            JsonTokenKind::True => JsonValue::Bool(true),
+            JsonTokenKind::False => JsonValue::Bool(false),
+            JsonTokenKind::Number if number::is_non_finite(token.text()) => {
+                JsonValue::String(token.text().to_owned())
+            }
+            JsonTokenKind::Number => JsonValue::Number(JsonNumber::from_token(token)?),
+            JsonTokenKind::String => JsonValue::String(token.as_str()?.into_owned()),
+            _ => JsonValue::Null,
+        })
+    }
+
+    fn name(token: &JsonToken<'s>) -> Result<String, ParseError> {
+        Ok(token.as_str()?.into_owned())
+    }
+
+    fn array(items: Vec<Self>) -> Self {
+        JsonValue::Array(items)
+    }
+
+    fn object(object: JsonObject) -> Self {
+        JsonValue::Object(object)
+    }
+
+    fn add(object: &mut JsonObject, name: String, value: Self, duplicate_keys: DuplicateKeys) {
+        match duplicate_keys {
+            DuplicateKeys::FirstWins if object.contains_key(&name) => {}
+            DuplicateKeys::KeepAll => object.push(name, value),
+            _ => {
+                object.insert(name, value);
+            }
+        }
+    }
+}
+
+impl JsonValue {
+    /// Parses JSON text using the default, lenient, [`ReaderOptions`]. Since
+    /// `NaN` and the infinities are then read as numbers but have no JSON
+    /// form, they are held as strings of their text, as in `"NaN"`.
+    pub fn parse(text: &str) -> Result<JsonValue, ParseError> {
+        Self::parse_with_options(text, ReaderOptions::default())
+    }
+
+    /// Parses JSON text, reading it to the end so that anything after the
+    /// value fails unless [`ReaderOptions::trailing_content`] allows it.
+    /// `NaN` and the infinities, read as numbers when
+    /// [`ReaderOptions::lenient_numbers`] is enabled, are held as strings.
+    pub fn parse_with_options(text: &str, options: ReaderOptions) -> Result<JsonValue, ParseError> {
+        ValueBuilder::parse(text, options)
+    }
+
+    /// Reads the next value from a reader, leaving the reader just past it.
+    /// The reader must not be positioned at the end of an array or object.
+    /// Members with duplicate names are handled as set by
+    /// [`ReaderOptions::duplicate_keys`]. Whatever follows a value at the
+    /// top level is left unread, so it is not checked against
+    /// [`ReaderOptions::trailing_content`] until the reader is read on.
+    pub fn from_reader(reader: &mut JsonTextReader<'_>) -> Result<JsonValue, ParseError> {
+        ValueBuilder::read(reader)
+    }
+
+    /// Writes the value to a writer, laid out as set by its options.
+    pub fn write_to<W: io::Write>(&self, writer: &mut JsonTextWriter<W>) -> Result<(), WriteError> {
+        match self {
+            JsonValue::Null => writer.write_null(),
+            JsonValue::Bool(value) => writer.write_bool(*value),
+            JsonValue::Number(number) => writer.write_raw_number(number.as_str()),
+            JsonValue::String(value) => writer.write_string(value),
+            JsonValue::Array(items) => {
+                writer.write_start_array()?;
+                for item in items {
+                    item.write_to(writer)?;
+                }
+                writer.write_end_array()
+            }
+            JsonValue::Object(object) => {
+                writer.write_start_object()?;
+                for (name, value) in object.iter() {
+                    writer.write_member(name)?;
+                    value.write_to(writer)?;
+                }
+                writer.write_end_object()
+            }
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&JsonNumber> {
+        match self {
+            JsonValue::Number(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    /// Returns a number as an integer if it converts without loss, as with
+    /// [`JsonNumber::as_i64`].
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().and_then(|n| n.as_i64().ok())
+    }
+
+    /// Returns a number as an integer if it converts without loss, as with
+    /// [`JsonNumber::as_u64`].
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(|n| n.as_u64().ok())
+    }
+
+    /// Returns a number as the nearest floating-point number, as with
+    /// [`JsonNumber::as_f64`].
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().and_then(|n| n.as_f64().ok())
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&JsonObject> {
+        match self {
+            JsonValue::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut JsonObject> {
+        match self {
+            JsonValue::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a member if this is an object that has it.
+    pub fn get(&self, name: &str) -> Option<&JsonValue> {
+        self.as_object().and_then(|object| object.get(name))
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut JsonValue> {
+        self.as_object_mut().and_then(|object| object.get_mut(name))
+    }
+
+    /// Returns an element if this is an array that has it.
+    pub fn at(&self, index: usize) -> Option<&JsonValue> {
+        self.as_array().and_then(|items| items.get(index))
+    }
+
+    pub fn at_mut(&mut self, index: usize) -> Option<&mut JsonValue> {
+        self.as_array_mut().and_then(|items| items.get_mut(index))
+    }
+
+    /// Looks up a value by a JSON Pointer (RFC 6901), like `/servers/3/port`,
+    /// where `~1` stands for `/` and `~0` for `~` in a name. The empty
+    /// pointer refers to the value itself. Returns `None` if there is no such
+    /// value or the pointer is invalid.
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue> {
+        pointer::get(self, &pointer::split(pointer)?)
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JsonValue> {
+        pointer::get_mut(self, &pointer::split(pointer)?)
+    }
+}
+
+/// Whether two values are equal as JSON Patch (RFC 6902) defines it: numbers
+/// are equal if their values are, however written, and objects are equal if
+/// they have the same members, in any order.
+pub(super) fn equivalent(a: &JsonValue, b: &JsonValue) -> bool {
+    match (a, b) {
+        (JsonValue::Number(a), JsonValue::Number(b)) => {
+            a.as_str() == b.as_str()
+                || match (a.as_i128(), b.as_i128()) {
+                    (Ok(a), Ok(b)) => a == b,
+                    _ => matches!((a.as_f64(), b.as_f64()), (Ok(a), Ok(b)) if a == b),
+                }
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equivalent(a, b))
+        }
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            a.len() == b.len()
+                && a.iter().all(|(name, a)| b.get(name).map_or(false, |b| equivalent(a, b)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+static NULL: JsonValue = JsonValue::Null;
+
+/// Returns the value of a member, or [`JsonValue::Null`] if this is not an
+/// object or it has no such member, so that lookups can be chained.
+impl Index<&str> for JsonValue {
+    type Output = JsonValue;
+
+    fn index(&self, name: &str) -> &JsonValue {
+        self.get(name).unwrap_or(&NULL)
+    }
+}
+
+/// Returns the value of a member, adding it as null if the object has no
+/// such member.
+///
+/// # Panics
+///
+/// Panics if this is not an object.
+impl IndexMut<&str> for JsonValue {
+    fn index_mut(&mut self, name: &str) -> &mut JsonValue {
+        let Some(object) = self.as_object_mut() else {
+            panic!("cannot index into a JSON value that is not an object");
+        };
+        if !object.contains_key(name) {
+            object.insert(name, JsonValue::Null);
+        }
+        object.get_mut(name).expect("member was just added")
+    }
+}
+
+/// Returns an element, or [`JsonValue::Null`] if this is not an array or
+/// the index is out of range, so that lookups can be chained.
+impl Index<usize> for JsonValue {
+    type Output = JsonValue;
+
+    fn index(&self, index: usize) -> &JsonValue {
+        self.at(index).unwrap_or(&NULL)
+    }
+}
+
+/// # Panics
+///
+/// Panics if this is not an array or the index is out of range.
+impl IndexMut<usize> for JsonValue {
+    fn index_mut(&mut self, index: usize) -> &mut JsonValue {
+        match self.as_array_mut() {
+            Some(items) => &mut items[index],
+            None => panic!("cannot index into a JSON value that is not an array"),
+        }
+    }
+}
+
+/// Writes the value as JSON text without any whitespace or, with the
+/// alternate flag (`{:#}`), laid out as with [`super::WriterOptions::pretty`].
+impl Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writer::display(f, |writer| self.write_to(writer))
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(value: bool) -> Self {
+        JsonValue::Bool(value)
+    }
+}
+
+/// Converts a finite number, while `NaN` and the infinities, which have no
+/// JSON form, become [`JsonValue::Null`].
+impl From<f64> for JsonValue {
+    fn from(value: f64) -> Self {
+        JsonNumber::from_f64(value).map_or(JsonValue::Null, JsonValue::Number)
+    }
+}
+
+impl From<JsonNumber> for JsonValue {
+    fn from(value: JsonNumber) -> Self {
+        JsonValue::Number(value)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(value: &str) -> Self {
+        JsonValue::String(value.to_owned())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(value: String) -> Self {
+        JsonValue::String(value)
+    }
+}
+
+impl From<Vec<JsonValue>> for JsonValue {
+    fn from(value: Vec<JsonValue>) -> Self {
+        JsonValue::Array(value)
+    }
+}
+
+impl From<JsonObject> for JsonValue {
+    fn from(value: JsonObject) -> Self {
+        JsonValue::Object(value)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(JsonValue::Null, Into::into)
+    }
+}
+
+impl FromStr for JsonValue {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_tree_with_accessors() {
+        let value = JsonValue::parse(
+            r#"{"a": [1, -2.5, "x", true, null], "b": {"c": 18446744073709551615}}"#,
+        )
+        .unwrap();
+        let items = value["a"].as_array().unwrap();
+        assert_eq!(items[0].as_i64(), Some(1));
+        assert_eq!(items[1].as_f64(), Some(-2.5));
+        assert_eq!(items[1].as_i64(), None);
+        assert_eq!(items[2].as_str(), Some("x"));
+        assert_eq!(items[3].as_bool(), Some(true));
+        assert!(items[4].is_null());
+        assert_eq!(value["b"]["c"].as_u64(), Some(u64::MAX));
+        assert_eq!(value["b"]["c"].as_i64(), None);
+        assert!(value["b"]["nope"][3].is_null());
+        assert_eq!(value.get("a").and_then(|a| a.at(2)), Some(&JsonValue::from("x")));
+        assert_eq!(value.at(0), None);
+    }
+
+    #[test]
+    fn edits_through_index() {
+        let mut value = JsonValue::parse(r#"{"a": [1, 2]}"#).unwrap();
+        value["a"][1] = JsonValue::from("two");
+        value["b"] = JsonValue::from(Some(true));
+        value.get_mut("a").unwrap().as_array_mut().unwrap().push(JsonValue::from(None::<i32>));
+        assert_eq!(value.to_string(), r#"{"a":[1,"two",null],"b":true}"#);
+    }
+
+    #[test]
+    #[should_panic(expected = "not an object")]
+    fn index_mut_panics_for_non_object() {
+        let mut value = JsonValue::from(1);
+        value["a"] = JsonValue::Null;
+    }
+
+    #[test]
+    fn numbers_keep_their_digits() {
+        let value: JsonValue = "[12345678901234567890123, 0x1F, 1.50]".parse().unwrap();
+        assert_eq!(value.to_string(), "[12345678901234567890123,31,1.50]");
+        assert_eq!(JsonValue::from(0.1).to_string(), "0.1");
+        assert_eq!(JsonValue::from(f64::NAN), JsonValue::Null);
+        assert_eq!("+.5".parse::<JsonNumber>().unwrap().as_str(), "0.5");
+        assert_eq!("x".parse::<JsonNumber>(), Err(NumberError::NotANumber));
+        assert_eq!(JsonNumber::from(u128::MAX).as_u64(), Err(NumberError::Overflow));
+    }
+
+    #[test]
+    fn from_reader_reads_one_value_at_a_time() {
+        let mut reader = JsonTextReader::new(r#"[{"a": 1}, [2], 3]"#);
+        Iterator::next(&mut reader).unwrap().unwrap();
+        let mut values = Vec::new();
+        for _ in 0..3 {
+            values.push(JsonValue::from_reader(&mut reader).unwrap().to_string());
+        }
+        assert_eq!(values, [r#"{"a":1}"#, "[2]", "3"]);
+        let err = JsonValue::from_reader(&mut reader).unwrap_err();
+        assert_eq!((err.kind(), err.position().column()), (SyntaxError::MissingValue, 18));
+        let err = JsonValue::parse("[1, ").unwrap_err();
+        assert_eq!(err.kind(), SyntaxError::UnterminatedArray);
+        let err = JsonValue::parse("").unwrap_err();
+        assert_eq!(err.kind(), SyntaxError::MissingValue);
+    }
+
+    #[test]
+    fn parse_enforces_trailing_content() {
+        let err = JsonValue::parse_with_options("[1] x", ReaderOptions::strict()).unwrap_err();
+        assert_eq!(err.kind(), SyntaxError::TrailingContent);
+        assert_eq!(err.position().column(), 5);
+        let err = JsonValue::parse_with_options("[1] [2]", ReaderOptions::strict()).unwrap_err();
+        assert_eq!(err.kind(), SyntaxError::TrailingContent);
+        let value = JsonValue::parse_with_options("[1] \n", ReaderOptions::strict()).unwrap();
+        assert_eq!(value, JsonValue::Array(vec![1.into()]));
+        assert_eq!(JsonValue::parse("[1] x").unwrap(), JsonValue::Array(vec![1.into()]));
+    }
+
+    #[test]
+    fn holds_non_finite_numbers_as_strings() {
+        let value = JsonValue::parse("[NaN]").unwrap();
+        assert_eq!(value, JsonValue::Array(vec!["NaN".into()]));
+        let options = ReaderOptions::default().lenient_numbers(false);
+        assert_eq!(JsonValue::parse_with_options("[NaN]", options).unwrap(), value);
+        let value = JsonValue::parse("[0x1F, 017, -Infinity]").unwrap();
+        assert_eq!(value, JsonValue::Array(vec![31.into(), 15.into(), "-Infinity".into()]));
+        let err = JsonValue::parse("[0x1F, 017, 08, Infinity]").unwrap_err();
+        assert_eq!((err.kind(), err.position().column()), (SyntaxError::InvalidNumber, 13));
+    }
+
+    #[test]
+    fn from_reader_leaves_later_values_unread() {
+        let mut reader = JsonTextReader::with_options("[1] x", ReaderOptions::strict());
+        assert_eq!(JsonValue::from_reader(&mut reader).unwrap(), JsonValue::Array(vec![1.into()]));
+        let err = Iterator::next(&mut reader).unwrap().unwrap_err();
+        assert_eq!(err.kind(), SyntaxError::TrailingContent);
+    }
+}
@@ -0,0 +1,889 @@
+// Copyright (c) 2005, 2022 Atif Aziz. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::{error::Error, fmt, fmt::Display, io};
+
+use super::{is_number, number, JsonNumber, JsonToken, JsonTokenKind, ParseError};
+
+#[derive(Debug)]
+pub enum WriteError {
+    Io(io::Error),
+    /// A value was written inside an object without a member name first.
+    MissingMemberName,
+    /// A member name was written outside an object or right after another.
+    UnexpectedMemberName,
+    /// An array or object was ended that is not the innermost one open, or
+    /// an object was ended right after a member name.
+    UnbalancedEnd,
+    /// A value was written after the one at the top level was complete.
+    TrailingValue,
+    /// The text given for a number is not in the form RFC 8259 specifies.
+    InvalidNumber,
+}
+
+impl Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteError::Io(err) => err.fmt(f),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+impl Error for WriteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WriteError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WriteError {
+    fn from(err: io::Error) -> Self {
+        WriteError::Io(err)
+    }
+}
+
+/// An error copying tokens with [`copy_tokens`], either reading them, of type
+/// `E`, or writing them.
+#[derive(Debug)]
+pub enum CopyError<E = ParseError> {
+    Read(E),
+    Write(WriteError),
+}
+
+impl<E: Display> Display for CopyError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CopyError::Read(err) => err.fmt(f),
+            CopyError::Write(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for CopyError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CopyError::Read(err) => Some(err),
+            CopyError::Write(err) => Some(err),
+        }
+    }
+}
+
+impl<E> From<WriteError> for CopyError<E> {
+    fn from(err: WriteError) -> Self {
+        CopyError::Write(err)
+    }
+}
+
+/// Controls the layout of the text written by [`JsonTextWriter`]. The
+/// default writes no whitespace at all whereas [`WriterOptions::pretty`]
+/// writes each array element and object member on a line of its own,
+/// indented by two spaces per level.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriterOptions {
+    indent: Option<String>,
+    compact: bool,
+    space_after_colon: bool,
+    space_after_comma: bool,
+    max_line_width: Option<usize>,
+    inline_arrays: bool,
+    ascii_only: bool,
+    html_safe: bool,
+    canonical: bool,
+}
+
+impl WriterOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options for text that is easy to read, as described for
+    /// [`WriterOptions`], with a space after each `:` and lines of up to 80
+    /// characters.
+    pub fn pretty() -> Self {
+        Self::default()
+            .indent_width(2)
+            .space_after_colon(true)
+            .space_after_comma(true)
+            .max_line_width(80)
+    }
+
+    /// Write each array element and object member on a line of its own,
+    /// indented by the given string for each level of nesting.
+    pub fn indent(mut self, value: &str) -> Self {
+        self.indent = Some(value.to_owned());
+        self
+    }
+
+    /// Like [`WriterOptions::indent`] with the given number of spaces.
+    pub fn indent_width(self, width: usize) -> Self {
+        self.indent(&" ".repeat(width))
+    }
+
+    /// Write no whitespace at all, whatever the other options say.
+    pub fn compact(mut self, value: bool) -> Self {
+        self.compact = value;
+        self
+    }
+
+    /// Write a space after the `:` that follows a member name.
+    pub fn space_after_colon(mut self, value: bool) -> Self {
+        self.space_after_colon = value;
+        self
+    }
+
+    /// Write a space after a `,` that is not at the end of a line.
+    pub fn space_after_comma(mut self, value: bool) -> Self {
+        self.space_after_comma = value;
+        self
+    }
+
+    /// The width in characters, including indentation, that lines may not
+    /// exceed wherever the layout leaves a choice. Without indentation, a
+    /// line is broken after the `,` that comes before an element or member
+    /// that would not fit on it. With indentation, each element and member
+    /// is on a line of its own anyway, and an array is only kept on one
+    /// line by [`WriterOptions::inline_arrays`] if it fits. A line can still
+    /// be longer by the brackets and `,` that end what is on it, or when a
+    /// single element or member is. There is no limit by default, nor when
+    /// compact or canonical.
+    pub fn max_line_width(mut self, value: usize) -> Self {
+        self.max_line_width = Some(value);
+        self
+    }
+
+    /// When indenting, keep an array on one line if it has no arrays or
+    /// objects in it and fits within [`WriterOptions::max_line_width`], if
+    /// set.
+    pub fn inline_arrays(mut self, value: bool) -> Self {
+        self.inline_arrays = value;
+        self
+    }
+
+    /// Escape every character above U+007F in strings, as `\uXXXX` or as a
+    /// surrogate pair of such, so the output is 7-bit ASCII.
+    pub fn ascii_only(mut self, value: bool) -> Self {
+        self.ascii_only = value;
+        self
+    }
+
+    /// Escape `<`, `>`, `&`, U+2028 and U+2029 in strings so the output can
+    /// be embedded in HTML, as in a `<script>` element, and read as
+    /// JavaScript.
+    pub fn html_safe(mut self, value: bool) -> Self {
+        self.html_safe = value;
+        self
+    }
+
+    /// Write the canonical form specified by RFC 8785, the JSON
+    /// Canonicalization Scheme, whatever the other options say. There is no
+    /// whitespace, object members are sorted by their names compared as
+    /// UTF-16 code units, strings only have the characters escaped that must
+    /// be and numbers are written as ECMAScript does, with
+    /// the fewest digits that round-trip, so `1.0` and `1e0` are both `1`.
+    /// A number too large for an IEEE 754 double is
+    /// [`WriteError::InvalidNumber`].
+    ///
+    /// Sorting means that each object is held in memory until its end.
+    pub fn canonical(mut self, value: bool) -> Self {
+        self.canonical = value;
+        self
+    }
+
+    fn escaping(&self) -> Escaping {
+        match self.canonical {
+            true => Escaping::default(),
+            false => Escaping { ascii_only: self.ascii_only, html_safe: self.html_safe },
+        }
+    }
+
+    /// Whether whitespace may be written at all.
+    fn spaced(&self) -> bool {
+        !self.compact && !self.canonical
+    }
+
+    fn indent_str(&self) -> Option<&str> {
+        self.indent.as_deref().filter(|_| self.spaced())
+    }
+
+    /// The width to which lines are broken between elements and members,
+    /// which is only done without indentation.
+    fn wrap_width(&self) -> Option<usize> {
+        self.max_line_width.filter(|_| self.spaced() && self.indent.is_none())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Container {
+    Array,
+    Object,
+}
+
+/// An array or object being written.
+#[derive(Debug)]
+struct Frame {
+    container: Container,
+    /// The number of elements or members written so far.
+    length: usize,
+    /// Whether a member name was written whose value is yet to be.
+    named: bool,
+}
+
+/// An array being held back, in case it can be kept on one line, with the
+/// text of its elements so far and its width on one line, brackets
+/// included, starting at `column`.
+#[derive(Debug)]
+struct Inline {
+    column: usize,
+    items: Vec<String>,
+    width: usize,
+}
+
+/// Writes JSON text to an [`io::Write`], one token at a time, with calls
+/// named after the kinds of tokens that [`super::JsonTextReader`] reads.
+/// Calls are checked to be made in an order that makes valid JSON text,
+/// failing otherwise without writing anything. Since each call makes small
+/// writes, the output should be buffered, as with an [`io::BufWriter`].
+#[derive(Debug)]
+pub struct JsonTextWriter<W> {
+    inner: W,
+    options: WriterOptions,
+    stack: Vec<Frame>,
+    /// Whether the value at the top level was written completely.
+    complete: bool,
+    /// The number of characters written since the last line break.
+    column: usize,
+    inline: Option<Inline>,
+    /// In canonical mode, the name and text of each member written so far
+    /// of each object open, to be sorted at its end.
+    members: Vec<Vec<(String, String)>>,
+    /// When breaking lines, the text of a member name, with the `:` after
+    /// it, held back until the width of its value is known.
+    name: Option<String>,
+}
+
+impl<W: io::Write> JsonTextWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_options(inner, WriterOptions::default())
+    }
+
+    pub fn with_options(inner: W, options: WriterOptions) -> Self {
+        Self {
+            inner,
+            options,
+            stack: Vec::new(),
+            complete: false,
+            column: 0,
+            inline: None,
+            members: Vec::new(),
+            name: None,
+        }
+    }
+
+    pub fn options(&self) -> &WriterOptions {
+        &self.options
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// The number of arrays and objects open.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Whether a value was written completely at the top level, so that the
+    /// output is JSON text.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    pub fn write_null(&mut self) -> Result<(), WriteError> {
+        self.write_scalar("null")
+    }
+
+    pub fn write_bool(&mut self, value: bool) -> Result<(), WriteError> {
+        self.write_scalar(if value { "true" } else { "false" })
+    }
+
+    pub fn write_number(&mut self, value: impl Into<JsonNumber>) -> Result<(), WriteError> {
+        self.write_raw_number(value.into().as_str())
+    }
+
+    /// Writes a number given as text, which must be in the form specified
+    /// by RFC 8259, as returned by [`JsonNumber::as_str`] and
+    /// [`super::JsonToken::as_decimal_str`].
+    pub fn write_raw_number(&mut self, text: &str) -> Result<(), WriteError> {
+        if !is_number(text) {
+            return Err(WriteError::InvalidNumber);
+        }
+        if self.options.canonical {
+            let value = number::to_f64(text).map_err(|_| WriteError::InvalidNumber)?;
+            return self.write_scalar(&number::to_es_string(value));
+        }
+        self.write_scalar(text)
+    }
+
+    /// Writes a token as read by [`super::JsonTextReader`] in the form
+    /// specified by RFC 8259, so strings are written in double quotes, with
+    /// only the characters escaped that must be, and numbers in decimal. A
+    /// number that has no such form, like `NaN`, is
+    /// [`WriteError::InvalidNumber`]. Reading the value of a string can fail
+    /// with a [`ParseError`]. Comments and whitespace are skipped.
+    pub fn write_token(&mut self, token: &JsonToken<'_>) -> Result<(), CopyError> {
+        match token.kind() {
+            JsonTokenKind::Null => self.write_null()?,
+            JsonTokenKind::True => self.write_bool(true)?,
+            JsonTokenKind::False => self.write_bool(false)?,
+            JsonTokenKind::Number => match token.as_decimal_str() {
+                Ok(text) => self.write_raw_number(&text)?,
+                Err(_) => Err(WriteError::InvalidNumber)?,
+            },
+            JsonTokenKind::String => {
+                self.write_string(&token.as_str().map_err(CopyError::Read)?)?;
+            }
+            JsonTokenKind::ArrayStart => self.write_start_array()?,
+            JsonTokenKind::ArrayEnd => self.write_end_array()?,
+            JsonTokenKind::ObjectStart => self.write_start_object()?,
+            JsonTokenKind::ObjectEnd => self.write_end_object()?,
+            JsonTokenKind::ObjectMember => {
+                self.write_member(&token.as_str().map_err(CopyError::Read)?)?;
+            }
+            JsonTokenKind::Comment | JsonTokenKind::Whitespace => {}
+        }
+        Ok(())
+    }
+
+    pub fn write_string(&mut self, value: &str) -> Result<(), WriteError> {
+        if self.inline.is_some() {
+            let mut text = String::new();
+            escape(value, self.options.escaping(), |s| {
+                text.push_str(s);
+                true
+            });
+            return self.write_scalar(&text);
+        }
+        let mut width = 0;
+        if self.options.wrap_width().is_some() {
+            escape(value, self.options.escaping(), |s| {
+                width += s.chars().count();
+                true
+            });
+        }
+        self.begin_value(width)?;
+        self.put_escaped(value)?;
+        self.end_value();
+        Ok(())
+    }
+
+    pub fn write_start_array(&mut self) -> Result<(), WriteError> {
+        self.write_start(Container::Array, "[")
+    }
+
+    pub fn write_end_array(&mut self) -> Result<(), WriteError> {
+        self.write_end(Container::Array, "]")
+    }
+
+    pub fn write_start_object(&mut self) -> Result<(), WriteError> {
+        self.write_start(Container::Object, "{")
+    }
+
+    pub fn write_end_object(&mut self) -> Result<(), WriteError> {
+        self.write_end(Container::Object, "}")
+    }
+
+    /// Writes the name of a member of the object being written, which must
+    /// be followed by its value.
+    pub fn write_member(&mut self, name: &str) -> Result<(), WriteError> {
+        let length = match self.stack.last() {
+            Some(Frame { container: Container::Object, named: false, length }) => *length,
+            _ => return Err(WriteError::UnexpectedMemberName),
+        };
+        if let Some(frame) = self.stack.last_mut() {
+            frame.named = true;
+        }
+        match self.members.last_mut() {
+            Some(members) => members.push((name.to_owned(), String::new())),
+            None if self.options.wrap_width().is_some() => {
+                let mut text = String::new();
+                escape(name, self.options.escaping(), |s| {
+                    text.push_str(s);
+                    true
+                });
+                text.push(':');
+                if self.options.space_after_colon {
+                    text.push(' ');
+                }
+                self.name = Some(text);
+                return Ok(());
+            }
+            None => self.put_separator(length, 0)?,
+        }
+        self.put_escaped(name)?;
+        self.put(":")?;
+        if self.options.space_after_colon && self.options.spaced() {
+            self.put(" ")?;
+        }
+        Ok(())
+    }
+
+    fn write_scalar(&mut self, text: &str) -> Result<(), WriteError> {
+        self.begin_value(text.chars().count())?;
+        match &mut self.inline {
+            Some(inline) => {
+                let separator = if self.options.space_after_comma { 2 } else { 1 };
+                if !inline.items.is_empty() {
+                    inline.width += separator;
+                }
+                inline.width += text.chars().count();
+                inline.items.push(text.to_owned());
+                if matches!(self.options.max_line_width, Some(max) if inline.column + inline.width > max)
+                {
+                    self.flush_inline()?;
+                }
+            }
+            None => self.put(text)?,
+        }
+        self.end_value();
+        Ok(())
+    }
+
+    fn write_start(&mut self, container: Container, punctuator: &str) -> Result<(), WriteError> {
+        self.flush_inline()?;
+        self.begin_value(punctuator.len())?;
+        if container == Container::Array
+            && self.options.inline_arrays
+            && self.options.indent_str().is_some()
+        {
+            self.inline = Some(Inline { column: self.column, items: Vec::new(), width: 2 });
+        } else {
+            self.put(punctuator)?;
+        }
+        self.stack.push(Frame { container, length: 0, named: false });
+        if container == Container::Object && self.options.canonical {
+            self.members.push(Vec::new());
+        }
+        Ok(())
+    }
+
+    fn write_end(&mut self, container: Container, punctuator: &str) -> Result<(), WriteError> {
+        let length = match self.stack.last() {
+            Some(frame) if frame.container == container && !frame.named => frame.length,
+            _ => return Err(WriteError::UnbalancedEnd),
+        };
+        if container == Container::Object && self.options.canonical {
+            let mut members = self.members.pop().expect("an object being sorted");
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            for (i, (_, text)) in members.iter().enumerate() {
+                if i > 0 {
+                    self.put(",")?;
+                }
+                self.put(text)?;
+            }
+        } else if let Some(inline) = self.inline.take() {
+            let separator = if self.options.space_after_comma { ", " } else { "," };
+            self.put("[")?;
+            self.put(&inline.items.join(separator))?;
+        } else if length > 0 {
+            self.put_line_break(self.stack.len() - 1)?;
+        }
+        self.put(punctuator)?;
+        self.stack.pop();
+        self.end_value();
+        Ok(())
+    }
+
+    /// Writes out an array that was held back, one element per line, as it
+    /// turned out that it cannot be kept on one line.
+    fn flush_inline(&mut self) -> Result<(), WriteError> {
+        let Some(inline) = self.inline.take() else {
+            return Ok(());
+        };
+        self.put("[")?;
+        for (i, item) in inline.items.iter().enumerate() {
+            self.put_separator(i, 0)?;
+            self.put(item)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that a value can be written next and writes the separator
+    /// that goes before it, if any, given the width of its text or of its
+    /// opening bracket.
+    fn begin_value(&mut self, width: usize) -> Result<(), WriteError> {
+        match self.stack.last() {
+            None if self.complete => Err(WriteError::TrailingValue),
+            None => Ok(()),
+            Some(Frame { container: Container::Array, .. }) if self.inline.is_some() => Ok(()),
+            Some(&Frame { container: Container::Array, length, .. }) => {
+                Ok(self.put_separator(length, width)?)
+            }
+            Some(&Frame { named: true, length, .. }) => match self.name.take() {
+                Some(name) => {
+                    self.put_separator(length, name.chars().count() + width)?;
+                    Ok(self.put(&name)?)
+                }
+                None => Ok(()),
+            },
+            Some(Frame { named: false, .. }) => Err(WriteError::MissingMemberName),
+        }
+    }
+
+    /// Counts a value that was written in the array or object being written,
+    /// or completes the output at the top level.
+    fn end_value(&mut self) {
+        match self.stack.last_mut() {
+            Some(frame) => {
+                frame.length += 1;
+                frame.named = false;
+            }
+            None => self.complete = true,
+        }
+    }
+
+    /// Writes what goes before the element or member that follows the given
+    /// number of others in the innermost array or object, given the width
+    /// of its first line as far as it is known when it begins.
+    fn put_separator(&mut self, length: usize, width: usize) -> io::Result<()> {
+        if length > 0 {
+            self.put(",")?;
+        }
+        let space = length > 0 && self.options.space_after_comma && self.options.spaced();
+        let fits = |max| self.column + usize::from(space) + width <= max;
+        if self.options.indent_str().is_some() {
+            self.put_line_break(self.stack.len())
+        } else if !self.options.wrap_width().map_or(true, fits) {
+            self.put("\n")?;
+            self.column = 0;
+            Ok(())
+        } else if space {
+            self.put(" ")
+        } else {
+            Ok(())
+        }
+    }
+
+    fn put_line_break(&mut self, depth: usize) -> io::Result<()> {
+        let Some(indent) = self.options.indent_str() else {
+            return Ok(());
+        };
+        let indent = indent.repeat(depth);
+        self.put("\n")?;
+        self.column = 0;
+        self.put(&indent)
+    }
+
+    fn put_escaped(&mut self, s: &str) -> io::Result<()> {
+        let mut result = Ok(());
+        escape(s, self.options.escaping(), |text| {
+            result = self.put(text);
+            result.is_ok()
+        });
+        result
+    }
+
+    /// Writes text that has no line breaks, other than a line break alone,
+    /// or adds it to the member being written of the object being sorted.
+    fn put(&mut self, text: &str) -> io::Result<()> {
+        if let Some((_, member)) = self.members.last_mut().and_then(|members| members.last_mut()) {
+            member.push_str(text);
+            return Ok(());
+        }
+        self.column += text.chars().count();
+        self.inner.write_all(text.as_bytes())
+    }
+}
+
+/// Writes the tokens read from a reader, such as a [`super::JsonTextReader`]
+/// or [`super::JsonStreamReader`], to a writer, as with
+/// [`JsonTextWriter::write_token`]. This turns any text the reader accepts
+/// into strict JSON, laid out as set by the options of the writer, without
+/// holding more than a token in memory.
+pub fn copy_tokens<'s, E, W>(
+    tokens: impl IntoIterator<Item = Result<JsonToken<'s>, E>>,
+    writer: &mut JsonTextWriter<W>,
+) -> Result<(), CopyError<E>>
+where
+    E: From<ParseError>,
+    W: io::Write,
+{
+    for token in tokens {
+        let token = token.map_err(CopyError::Read)?;
+        writer.write_token(&token).map_err(|err| match err {
+            CopyError::Read(err) => CopyError::Read(err.into()),
+            CopyError::Write(err) => CopyError::Write(err),
+        })?;
+    }
+    Ok(())
+}
+
+/// Which characters to escape in strings, besides quotes, backslashes and
+/// control characters, which must always be.
+#[derive(Debug, Copy, Clone, Default)]
+struct Escaping {
+    ascii_only: bool,
+    html_safe: bool,
+}
+
+/// Passes a string in quotes, escaped, to `write` in pieces, stopping early
+/// if `write` returns `false`.
+fn escape(s: &str, escaping: Escaping, mut write: impl FnMut(&str) -> bool) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    if !write("\"") {
+        return;
+    }
+    let mut start = 0;
+    for (i, ch) in s.char_indices() {
+        let mut buf = [0; 12];
+        let escaped = match ch {
+            '"' => Some("\\\""),
+            '\\' => Some("\\\\"),
+            '\x08' => Some("\\b"),
+            '\x0C' => Some("\\f"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            '\t' => Some("\\t"),
+            '<' | '>' | '&' | '\u{2028}' | '\u{2029}' if escaping.html_safe => None,
+            ch if ch < ' ' || ch > '\x7F' && escaping.ascii_only => None,
+            _ => continue,
+        };
+        let escaped = match escaped {
+            Some(escaped) => escaped,
+            None => {
+                //
+                // Escape as \uXXXX, using a surrogate pair for a character
+                // outside the Basic Multilingual Plane.
+                //
+                let mut units = [0; 2];
+                let units = ch.encode_utf16(&mut units);
+                for (unit, buf) in units.iter().zip(buf.chunks_mut(6)) {
+                    buf[..2].copy_from_slice(b"\\u");
+                    for (j, b) in buf[2..].iter_mut().enumerate() {
+                        *b = HEX[usize::from(*unit >> (12 - 4 * j)) & 0xF];
+                    }
+                }
+                std::str::from_utf8(&buf[..6 * units.len()]).expect("ASCII")
+            }
+        };
+        if !write(&s[start..i]) || !write(escaped) {
+            return;
+        }
+        start = i + ch.len_utf8();
+    }
+    if write(&s[start..]) {
+        write("\"");
+    }
+}
+
+/// Adapts a [`fmt::Formatter`] to be written to by a [`JsonTextWriter`],
+/// which only ever writes whole UTF-8 sequences.
+pub(super) struct FmtWriter<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl io::Write for FmtWriter<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = std::str::from_utf8(buf).map_err(|_| io::ErrorKind::InvalidData)?;
+        self.0.write_str(text).map_err(|_| io::ErrorKind::Other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Formats what `write` writes, which is laid out as with
+/// [`WriterOptions::pretty`] if the alternate flag (`{:#}`) is given and
+/// otherwise without any whitespace.
+pub(super) fn display(
+    f: &mut fmt::Formatter<'_>,
+    write: impl FnOnce(&mut JsonTextWriter<FmtWriter<'_, '_>>) -> Result<(), WriteError>,
+) -> fmt::Result {
+    let options = if f.alternate() { WriterOptions::pretty() } else { WriterOptions::default() };
+    write(&mut JsonTextWriter::with_options(FmtWriter(f), options)).map_err(|_| fmt::Error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{JsonTextReader, JsonValue, SyntaxError};
+
+    #[test]
+    fn writes_tokens_in_order() {
+        let mut writer = JsonTextWriter::new(Vec::new());
+        writer.write_start_object().unwrap();
+        writer.write_member("a\tb").unwrap();
+        writer.write_start_array().unwrap();
+        writer.write_number(-12).unwrap();
+        writer.write_raw_number("1.5e+3").unwrap();
+        writer.write_string("\"\\/\u{1}\u{7F}é").unwrap();
+        writer.write_bool(false).unwrap();
+        writer.write_null().unwrap();
+        assert_eq!(writer.depth(), 2);
+        writer.write_end_array().unwrap();
+        assert!(!writer.is_complete());
+        writer.write_end_object().unwrap();
+        assert!(writer.is_complete());
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "{\"a\\tb\":[-12,1.5e+3,\"\\\"\\\\/\\u0001\u{7F}é\",false,null]}"
+        );
+    }
+
+    #[test]
+    fn checks_call_order() {
+        let mut writer = JsonTextWriter::new(Vec::new());
+        assert!(matches!(writer.write_member("a"), Err(WriteError::UnexpectedMemberName)));
+        assert!(matches!(writer.write_end_array(), Err(WriteError::UnbalancedEnd)));
+        writer.write_start_object().unwrap();
+        assert!(matches!(writer.write_null(), Err(WriteError::MissingMemberName)));
+        assert!(matches!(writer.write_end_array(), Err(WriteError::UnbalancedEnd)));
+        writer.write_member("a").unwrap();
+        assert!(matches!(writer.write_member("b"), Err(WriteError::UnexpectedMemberName)));
+        assert!(matches!(writer.write_end_object(), Err(WriteError::UnbalancedEnd)));
+        assert!(matches!(writer.write_raw_number("01"), Err(WriteError::InvalidNumber)));
+        writer.write_start_array().unwrap();
+        assert!(matches!(writer.write_member("b"), Err(WriteError::UnexpectedMemberName)));
+        assert!(matches!(writer.write_end_object(), Err(WriteError::UnbalancedEnd)));
+        writer.write_end_array().unwrap();
+        writer.write_end_object().unwrap();
+        assert!(matches!(writer.write_bool(true), Err(WriteError::TrailingValue)));
+        assert!(matches!(writer.write_start_array(), Err(WriteError::TrailingValue)));
+        assert_eq!(writer.get_ref().as_slice(), br#"{"a":[]}"#);
+    }
+
+    fn write(text: &str, options: WriterOptions) -> String {
+        let mut writer = JsonTextWriter::with_options(Vec::new(), options);
+        JsonValue::parse(text).unwrap().write_to(&mut writer).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn pretty_options() {
+        let text = r#"{"a": [1, 2], "b": {}, "c": []}"#;
+        assert_eq!(write(text, WriterOptions::default()), r#"{"a":[1,2],"b":{},"c":[]}"#);
+        assert_eq!(
+            write(text, WriterOptions::pretty()),
+            "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {},\n  \"c\": []\n}"
+        );
+        let options = WriterOptions::pretty().compact(true);
+        assert_eq!(write(text, options), r#"{"a":[1,2],"b":{},"c":[]}"#);
+        let options = WriterOptions::new().space_after_colon(true).space_after_comma(true);
+        assert_eq!(write(text, options), r#"{"a": [1, 2], "b": {}, "c": []}"#);
+        let value = JsonValue::parse(text).unwrap();
+        assert_eq!(format!("{value:#}"), write(text, WriterOptions::pretty()));
+    }
+
+    #[test]
+    fn inline_arrays_fit_max_line_width() {
+        let options = WriterOptions::pretty().inline_arrays(true).max_line_width(16);
+        let text = r#"{"a": [1, 2, 3], "b": [10, 20, 30], "c": [[1]]}"#;
+        assert_eq!(
+            write(text, options),
+            concat!(
+                "{\n",
+                "  \"a\": [1, 2, 3],\n",
+                "  \"b\": [\n    10,\n    20,\n    30\n  ],\n",
+                "  \"c\": [\n    [1]\n  ]\n",
+                "}"
+            )
+        );
+    }
+
+    #[test]
+    fn max_line_width_breaks_lines_between_values() {
+        let text = r#"{"a": [100, 200, 300, 400], "bc": "de", "f": {"g": true}}"#;
+        let options = WriterOptions::new().space_after_comma(true).max_line_width(16);
+        assert_eq!(
+            write(text, options),
+            concat!(
+                "{\"a\":[100, 200,\n",
+                "300, 400],\n",
+                "\"bc\":\"de\", \"f\":{\n",
+                "\"g\":true}}"
+            )
+        );
+        let options = WriterOptions::new().max_line_width(1);
+        assert_eq!(write("[1,[2]]", options), "[\n1,\n[\n2]]");
+        let options = WriterOptions::pretty().inline_arrays(true).max_line_width(8);
+        assert_eq!(
+            write(r#"{"a long name": "a long value"}"#, options),
+            "{\n  \"a long name\": \"a long value\"\n}"
+        );
+        let options = WriterOptions::new().compact(true).max_line_width(1);
+        assert_eq!(write("[1,2]", options), "[1,2]");
+    }
+
+    fn copy(text: &str) -> Result<String, CopyError> {
+        let mut writer = JsonTextWriter::new(Vec::new());
+        copy_tokens(JsonTextReader::new(text), &mut writer)?;
+        Ok(String::from_utf8(writer.into_inner()).unwrap())
+    }
+
+    #[test]
+    fn copies_lenient_text_as_strict() {
+        let text = "// config\n{ name => 'it\\'s', 'a\"b': [0x1F, +.5, 1.,], /* c */ mode: fast; }";
+        assert_eq!(copy(text).unwrap(), r#"{"name":"it's","a\"b":[31,0.5,1],"mode":"fast"}"#);
+    }
+
+    #[test]
+    fn copy_fails_reading_or_writing() {
+        match copy("[1, }") {
+            Err(CopyError::Read(err)) => assert_eq!(err.kind(), SyntaxError::MissingValue),
+            other => panic!("unexpected: {other:?}"),
+        }
+        assert!(matches!(copy("[NaN]"), Err(CopyError::Write(WriteError::InvalidNumber))));
+    }
+
+    #[test]
+    fn writes_canonical_form() {
+        let options = WriterOptions::pretty().ascii_only(true).canonical(true);
+        let text = r#"{"b": [1.0, 1e0, -0, 1E21], "ﬁ": "é\u000f", "😀": 0, "a": {"z": 1, "y": 2}}"#;
+        let expected = "{\"a\":{\"y\":2,\"z\":1},\"b\":[1,1,0,1e+21],\"😀\":0,\"ﬁ\":\"é\\u000f\"}";
+        assert_eq!(write(text, options.clone()), expected);
+        let mut writer = JsonTextWriter::with_options(Vec::new(), options);
+        copy_tokens(JsonTextReader::new(text), &mut writer).unwrap();
+        assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), expected);
+    }
+
+    #[test]
+    fn canonical_numbers_must_fit_a_double() {
+        let mut writer =
+            JsonTextWriter::with_options(Vec::new(), WriterOptions::new().canonical(true));
+        assert!(matches!(writer.write_raw_number("1e400"), Err(WriteError::InvalidNumber)));
+        writer.write_raw_number("1e308").unwrap();
+        assert_eq!(writer.get_ref().as_slice(), b"1e+308");
+    }
+
+    #[test]
+    fn escapes_for_ascii_and_html() {
+        let text = r#"["é😀", "</script>&\u2028\u2029"]"#;
+        assert_eq!(write(text, WriterOptions::new()), "[\"é😀\",\"</script>&\u{2028}\u{2029}\"]");
+        assert_eq!(
+            write(text, WriterOptions::new().ascii_only(true)),
+            r#"["\u00e9\ud83d\ude00","</script>&\u2028\u2029"]"#
+        );
+        assert_eq!(
+            write(text, WriterOptions::new().html_safe(true)),
+            r#"["é😀","\u003c/script\u003e\u0026\u2028\u2029"]"#
+        );
+        let options = WriterOptions::pretty().inline_arrays(true).ascii_only(true).html_safe(true);
+        assert_eq!(
+            write(r#"{"<é>": ["&"]}"#, options),
+            "{\n  \"\\u003c\\u00e9\\u003e\": [\"\\u0026\"]\n}"
+        );
+    }
+}
@@ -1,11 +1,9 @@
-use std::{error::Error, io::Read};
+use std::error::Error;
 
 use jayrock::json::*;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut json = String::new();
-    _ = std::io::stdin().read_to_string(&mut json)?;
-    for token in JsonTextReader::new(json.as_str()) {
+    for token in JsonStreamReader::new(std::io::stdin().lock()) {
         println!("{token:?}");
     }
     Ok(())